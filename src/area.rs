@@ -0,0 +1,236 @@
+use crate::{Grib2Field, earth::EarthModel, grid::{GaussianGridDefinition, GridCoordinates, GridDefinition}};
+
+/*
+Grid cell areas and latitude weights, for area-weighted statistics.
+
+Cells of lat/lon and Gaussian grids are bounded by the parallels halfway between rows and by
+half the longitude increment either side of each point, and their areas are exact on the
+grid's earth model. Cells of projected grids are measured from the local metric of the
+earth at each point, which is exact in the limit of small cells.
+*/
+
+// q of the authalic latitude, Snyder (3-12), for a latitude in radians
+fn authalic_q(phi: f64, e: f64) -> f64 {
+    let sin = phi.sin();
+
+    if e < 1e-12 {
+        2.0 * sin
+    } else {
+        let es = e * sin;
+        (1.0 - e * e) * (sin / (1.0 - es * es) - ((1.0 - es) / (1.0 + es)).ln() / (2.0 * e))
+    }
+}
+
+// area in m^2 between two parallels over a width of longitude, all in degrees
+pub(crate) fn band_area(earth: &EarthModel, lat1: f64, lat2: f64, width: f64) -> f64 {
+    let (a, e) = (earth.semi_major_axis(), earth.eccentricity());
+    let q = |lat: f64| authalic_q(lat.to_radians(), e);
+
+    a * a * width.to_radians().abs() / 2.0 * (q(lat2) - q(lat1)).abs()
+}
+
+/*
+The parallels bounding each row, halfway between neighbouring rows and half a row spacing
+beyond the outer rows, within the poles. Rows covering every latitude of a global Gaussian
+grid reach all the way to the poles.
+*/
+fn row_edges(latitudes: &[f64], to_poles: bool) -> Vec<f64> {
+    let count = latitudes.len();
+    if count < 2 {
+        return latitudes.iter().flat_map(|lat| vec![*lat, *lat]).collect();
+    }
+
+    let mut edges: Vec<f64> = Vec::with_capacity(count + 1);
+    edges.push(latitudes[0] - (latitudes[1] - latitudes[0]) / 2.0);
+    edges.extend(latitudes.windows(2).map(|pair| (pair[0] + pair[1]) / 2.0));
+    edges.push(latitudes[count - 1] + (latitudes[count - 1] - latitudes[count - 2]) / 2.0);
+
+    if to_poles {
+        edges[0] = 90.0 * edges[0].signum();
+        edges[count] = 90.0 * edges[count].signum();
+    }
+
+    edges.iter().map(|edge| edge.clamp(-90.0, 90.0)).collect()
+}
+
+// whether the rows of a Gaussian grid cover every latitude, so that its outer cells reach the poles
+fn reaches_poles(def: &GaussianGridDefinition) -> bool {
+    def.row_count() == 2 * def.parallels_between_pole_and_equator.max(0) as usize
+}
+
+/*
+The cells of one row of a lat/lon or Gaussian grid: the parallels bounding the row, and the
+longitude of the first point with the signed step from point to point. Each cell is as wide
+as the step, centred on its point.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CellRow {
+    pub south: f64,
+    pub north: f64,
+    pub first_longitude: f64,
+    pub step: f64,
+    pub count: usize
+}
+
+// the rows of cells of a plain (unrotated) lat/lon or Gaussian grid, in scanning order
+pub(crate) fn cell_rows(grid: &GridDefinition) -> Option<Vec<CellRow>> {
+    let (latitudes, to_poles) = match grid {
+        GridDefinition::LatLon(def) => (def.latitudes(), false),
+        GridDefinition::Gaussian(def) => (def.latitudes(), reaches_poles(def)),
+        _ => return None
+    };
+
+    let counts = grid.row_point_counts()?;
+    let edges = row_edges(&latitudes, to_poles);
+
+    let increment = |row: usize| match grid {
+        GridDefinition::LatLon(def) => def.row_increment(row),
+        GridDefinition::Gaussian(def) => def.row_increment(row),
+        _ => 0.0
+    };
+
+    (0..latitudes.len())
+        .map(|row| Some(CellRow {
+            south: edges[row].min(edges[row + 1]),
+            north: edges[row].max(edges[row + 1]),
+            first_longitude: grid.latlon(0.0, row as f64)?.1,
+            step: increment(row),
+            count: *counts.get(row)?
+        }))
+        .collect()
+}
+
+// the cell areas of every point of a lat/lon or Gaussian grid in storage order, row by row
+fn row_cell_areas(grid: &GridDefinition, earth: &EarthModel, latitudes: Vec<f64>, to_poles: bool) -> Option<Vec<f64>> {
+    let counts = grid.row_point_counts()?;
+    let edges = row_edges(&latitudes, to_poles);
+
+    let increment = |row: usize| match grid {
+        GridDefinition::LatLon(def) => def.row_increment(row),
+        GridDefinition::RotatedLatLon(def) => def.rotated_grid.row_increment(row),
+        GridDefinition::Gaussian(def) => def.row_increment(row),
+        _ => 0.0
+    };
+
+    let areas: Vec<f64> = (0..latitudes.len()).map(|row| band_area(earth, edges[row], edges[row + 1], increment(row))).collect();
+
+    match (grid.grid_shape(), grid.scanning_mode()) {
+        (Some((ni, nj)), Some(mode)) => Some(mode.point_order(ni, nj).map(|(_, j)| areas[j]).collect()),
+        _ => Some(counts.iter().zip(areas.iter()).flat_map(|(count, area)| vec![*area; *count]).collect())
+    }
+}
+
+/*
+Area of the cell around grid point (i, j) of a projected grid, from the displacements on the
+earth between the points half a grid length either side along i and along j. Zero for
+points whose neighbourhood is not on the earth.
+*/
+fn projected_cell_area(grid: &GridDefinition, earth: &EarthModel, i: f64, j: f64) -> f64 {
+    let points = (
+        grid.latlon(i, j),
+        grid.latlon(i - 0.5, j),
+        grid.latlon(i + 0.5, j),
+        grid.latlon(i, j - 0.5),
+        grid.latlon(i, j + 0.5)
+    );
+
+    let (centre, west, east, south, north) = match points {
+        (Some(centre), Some(west), Some(east), Some(south), Some(north)) => (centre, west, east, south, north),
+        _ => return 0.0
+    };
+
+    // radii of curvature along the meridian and the prime vertical at the point
+    let (a, e) = (earth.semi_major_axis(), earth.eccentricity());
+    let phi = centre.0.to_radians();
+    let w = 1.0 - (e * phi.sin()).powi(2);
+    let (meridian, prime_vertical) = (a * (1.0 - e * e) / w.powf(1.5), a / w.sqrt());
+
+    // (eastward, northward) displacement in metres between two points
+    let displacement = |from: (f64, f64), to: (f64, f64)| {
+        let dlon = (to.1 - from.1 + 180.0).rem_euclid(360.0) - 180.0;
+        (
+            dlon.to_radians() * prime_vertical * phi.cos(),
+            (to.0 - from.0).to_radians() * meridian
+        )
+    };
+
+    let (u, v) = (displacement(west, east), displacement(south, north));
+    (u.0 * v.1 - u.1 * v.0).abs()
+}
+
+impl GridDefinition {
+    /*
+    The area in m^2 of the cell around every point of the grid, in storage order. Rotated
+    lat/lon grids are measured in their rotated coordinates, on a sphere of the earth's
+    equatorial radius as the rotation of the pole is only defined on a sphere.
+    None for the Arakawa staggered, unstructured and unsupported templates.
+    */
+    pub fn cell_areas(&self) -> Option<Vec<f64>> {
+        let earth = self.earth_model()?;
+
+        match self {
+            GridDefinition::LatLon(def) => row_cell_areas(self, &earth, def.latitudes(), false),
+            GridDefinition::RotatedLatLon(def) => {
+                let sphere = EarthModel::Sphere(earth.semi_major_axis());
+                row_cell_areas(self, &sphere, def.rotated_grid.latitudes(), false)
+            },
+            GridDefinition::Gaussian(def) => row_cell_areas(self, &earth, def.latitudes(), reaches_poles(def)),
+            GridDefinition::Mercator(_) |
+            GridDefinition::PolarStereographic(_) |
+            GridDefinition::LambertConformal(_) |
+            GridDefinition::SpaceView(_) => {
+                let (ni, nj) = self.grid_shape()?;
+                Some(
+                    self.scanning_mode()?
+                        .point_order(ni, nj)
+                        .map(|(i, j)| projected_cell_area(self, &earth, i as f64, j as f64))
+                        .collect()
+                )
+            },
+            _ => None
+        }
+    }
+}
+
+impl Grib2Field {
+    /*
+    The cosine of the latitude of every point in storage order, the usual weights for averages
+    over regular lat/lon grids. Points not on the earth get no weight. Unstructured grids need
+    attached coordinates.
+    */
+    pub fn cos_latitude_weights(&self) -> Option<Vec<f64>> {
+        let weight = |lat: &f64| if lat.is_nan() { 0.0 } else { lat.to_radians().cos().max(0.0) };
+
+        match self.coordinates()? {
+            GridCoordinates::Axes { latitudes, longitudes } => {
+                let mode = self.grid().scanning_mode()?;
+                Some(mode.point_order(longitudes.len(), latitudes.len()).map(|(_, j)| weight(&latitudes[j])).collect())
+            },
+            GridCoordinates::Points { latitudes, .. } => Some(latitudes.iter().map(weight).collect())
+        }
+    }
+
+    /*
+    The mean of the values present in the field, weighted by one weight per point in storage
+    order. None when no point with a value has any weight, or the weights do not cover the field.
+    */
+    pub fn weighted_mean(&self, weights: &[f64]) -> Option<f64> {
+        let values = self.data_masked();
+        if weights.len() != values.len() {
+            return None;
+        }
+
+        let (sum, total) = values
+            .iter()
+            .zip(weights.iter())
+            .filter_map(|(value, weight)| Some((value.as_ref()?, weight)))
+            .fold((0.0, 0.0), |acc, (value, weight)| (acc.0 + *value as f64 * weight, acc.1 + weight));
+
+        if total > 0.0 { Some(sum / total) } else { None }
+    }
+
+    // the mean of the field weighted by the area of each grid cell
+    pub fn area_weighted_mean(&self) -> Option<f64> {
+        self.weighted_mean(&self.grid().cell_areas()?)
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+/*
+A compact bit-vector for Section 6 bitmaps.
+
+Bits are stored most significant first, the same way they are laid out in Section 6,
+so that a set bit means the grid point has a value in the packed data.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitmap {
+    bits: Vec<u8>,
+    len: usize
+}
+
+impl Bitmap {
+    // creates a bitmap of the given length with every point present
+    pub fn new(len: usize) -> Bitmap {
+        let mut bits = vec![0xFF; (len + 7) / 8];
+
+        if len % 8 != 0 {
+            if let Some(last) = bits.last_mut() {
+                *last = 0xFF << (8 - len % 8);
+            }
+        }
+
+        Bitmap {
+            bits,
+            len
+        }
+    }
+
+    // g2c hands out bitmaps as one integer (0 or 1) per grid point
+    pub fn from_flags(flags: &[i64]) -> Bitmap {
+        let mut bitmap = Bitmap {
+            bits: vec![0; (flags.len() + 7) / 8],
+            len: flags.len()
+        };
+
+        for (i, flag) in flags.iter().enumerate() {
+            bitmap.set(i, *flag != 0);
+        }
+
+        bitmap
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // points outside of the bitmap are treated as missing
+    pub fn get(&self, point: usize) -> bool {
+        point < self.len && self.bits[point / 8] & (0x80 >> (point % 8)) != 0
+    }
+
+    pub fn set(&mut self, point: usize, present: bool) {
+        if point >= self.len {
+            return;
+        }
+
+        if present {
+            self.bits[point / 8] |= 0x80 >> (point % 8);
+        } else {
+            self.bits[point / 8] &= !(0x80 >> (point % 8));
+        }
+    }
+
+    // the number of points which have a value in the packed data
+    pub fn count_present(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |point| self.get(point))
+    }
+
+    pub fn to_flags(&self) -> Vec<i64> {
+        self.iter().map(|present| present as i64).collect()
+    }
+
+    pub fn as_bytes(&self) -> &Vec<u8> {
+        &self.bits
+    }
+}
+
+/*
+Predefined bitmaps, referred to by Section 6 bitmap indicators 1 through 253.
+Their meaning is set by the originating center, so they are registered per center.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct BitmapRegistry {
+    bitmaps: HashMap<(u16, u8), Bitmap>
+}
+
+impl BitmapRegistry {
+    pub fn new() -> BitmapRegistry {
+        BitmapRegistry {
+            bitmaps: HashMap::new()
+        }
+    }
+
+    // returns the bitmap previously registered under the same center and indicator, if any
+    pub fn register(&mut self, center: u16, indicator: u8, bitmap: Bitmap) -> Option<Bitmap> {
+        self.bitmaps.insert((center, indicator), bitmap)
+    }
+
+    pub fn get(&self, center: u16, indicator: u8) -> Option<&Bitmap> {
+        self.bitmaps.get(&(center, indicator))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bitmaps.is_empty()
+    }
+}
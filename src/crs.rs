@@ -0,0 +1,361 @@
+use crate::{
+    earth::EarthModel,
+    grid::{
+        GaussianGridDefinition,
+        GridDefinition,
+        LambertConformalGridDefinition,
+        LatLonGridDefinition,
+        MercatorGridDefinition,
+        PolarStereographicGridDefinition,
+        RotatedLatLonGridDefinition,
+        SpaceViewGridDefinition
+    }
+};
+
+/*
+Coordinate reference systems of grid definitions, for handing fields to PROJ and GDAL.
+
+Every supported template gives a PROJ string and an OGC WKT2 (2019) CRS. Grids which are
+regular in their CRS also give an affine geotransform in GDAL's order, mapping grid indices
+(i, j) to CRS coordinates of the corners of the grid cells:
+
+    x = transform[0] + i * transform[1] + j * transform[2]
+    y = transform[3] + i * transform[4] + j * transform[5]
+
+The indices count from the first grid point in the scanning directions, so the transform
+describes the data as stored whenever consecutive points run along i (scanning mode bit 3 unset).
+*/
+
+const DEGREE: &str = "ANGLEUNIT[\"degree\",0.0174532925199433]";
+const METRE: &str = "LENGTHUNIT[\"metre\",1]";
+
+// angles in the projected grid templates are stored in units of 10^-6 degrees
+fn degrees(value: i64) -> f64 {
+    value as f64 / 1e6
+}
+
+// longitudes in PROJ strings and WKT are given in [-180, 180)
+fn signed_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn proj_earth(earth: &EarthModel) -> String {
+    match earth {
+        EarthModel::Sphere(radius) => format!("+R={}", radius),
+        EarthModel::Wgs84 => String::from("+ellps=WGS84"),
+        EarthModel::Grs80 => String::from("+ellps=GRS80"),
+        EarthModel::Airy1830 => String::from("+ellps=airy"),
+        _ => format!("+a={} +b={}", earth.semi_major_axis(), earth.semi_minor_axis())
+    }
+}
+
+// the datum and prime meridian of a geographic CRS, WKT has no ellipsoid without a datum
+fn wkt_datum(earth: &EarthModel) -> String {
+    let (name, inverse_flattening) = match earth {
+        EarthModel::Sphere(_) => ("Sphere", 0.0),
+        EarthModel::Wgs84 => ("WGS 84", 298.257223563),
+        EarthModel::Grs80 => ("GRS 1980", 298.257222101),
+        EarthModel::Airy1830 => ("Airy 1830", 299.3249646),
+        EarthModel::Iau1965 => ("IAU 1965", 1.0 / earth.flattening()),
+        EarthModel::Oblate { .. } => ("unnamed", 1.0 / earth.flattening())
+    };
+
+    format!(
+        "DATUM[\"unnamed\",ELLIPSOID[\"{}\",{},{},{}]],PRIMEM[\"Greenwich\",0,{}]",
+        name, earth.semi_major_axis(), inverse_flattening, METRE, DEGREE)
+}
+
+fn wkt_ellipsoidal_cs() -> String {
+    format!(
+        "CS[ellipsoidal,2],AXIS[\"latitude\",north,ORDER[1],{}],AXIS[\"longitude\",east,ORDER[2],{}]",
+        DEGREE, DEGREE)
+}
+
+fn wkt_geographic(name: &str, earth: &EarthModel) -> String {
+    format!("GEOGCRS[\"{}\",{},{}]", name, wkt_datum(earth), wkt_ellipsoidal_cs())
+}
+
+// a parameter of a conversion, with its EPSG code where it has one
+fn wkt_parameter(name: &str, value: f64, unit: &str, epsg: Option<u32>) -> String {
+    match epsg {
+        Some(code) => format!("PARAMETER[\"{}\",{},{},ID[\"EPSG\",{}]]", name, value, unit, code),
+        None => format!("PARAMETER[\"{}\",{},{}]", name, value, unit)
+    }
+}
+
+// a projected CRS on the grid's earth, from the conversion method and its parameters
+fn wkt_projected(name: &str, earth: &EarthModel, method: &str, epsg: Option<u32>, parameters: &[String]) -> String {
+    let method = match epsg {
+        Some(code) => format!("METHOD[\"{}\",ID[\"EPSG\",{}]]", method, code),
+        None => format!("METHOD[\"{}\"]", method)
+    };
+
+    format!(
+        "PROJCRS[\"{}\",BASEGEOGCRS[\"unnamed\",{}],CONVERSION[\"unnamed\",{},{}],CS[Cartesian,2],\
+        AXIS[\"easting\",east,ORDER[1],{}],AXIS[\"northing\",north,ORDER[2],{}]]",
+        name, wkt_datum(earth), method, parameters.join(","), METRE, METRE)
+}
+
+// the false easting and northing, which g2r always leaves at zero
+fn wkt_false_origin(easting: &str, easting_epsg: u32, northing: &str, northing_epsg: u32) -> Vec<String> {
+    vec![
+        wkt_parameter(easting, 0.0, METRE, Some(easting_epsg)),
+        wkt_parameter(northing, 0.0, METRE, Some(northing_epsg))
+    ]
+}
+
+/*
+The geotransform of a grid from the CRS coordinates of grid point (i, j), which must be
+affine in i and j. The origin is moved back by half a cell to the corner of the first cell.
+*/
+fn affine<F>(position: F) -> Option<[f64; 6]>
+where
+    F: Fn(f64, f64) -> Option<(f64, f64)>
+{
+    let (x0, y0) = position(0.0, 0.0)?;
+    let (xi, yi) = position(1.0, 0.0)?;
+    let (xj, yj) = position(0.0, 1.0)?;
+
+    // rounding in the projections leaves traces of rotation on grids which have none
+    let cell = (xi - x0).hypot(yj - y0);
+    let snapped = |step: f64| if step.abs() < cell * 1e-9 { 0.0 } else { step };
+
+    let (dxi, dyi, dxj, dyj) = (xi - x0, snapped(yi - y0), snapped(xj - x0), yj - y0);
+
+    Some([
+        x0 - (dxi + dxj) / 2.0,
+        dxi,
+        dxj,
+        y0 - (dyi + dyj) / 2.0,
+        dyi,
+        dyj
+    ])
+}
+
+impl LatLonGridDefinition {
+    pub fn proj_string(&self) -> String {
+        format!("+proj=longlat {} +no_defs", proj_earth(&self.earth_model()))
+    }
+
+    pub fn wkt(&self) -> String {
+        wkt_geographic("GRIB2 grid definition template 3.0", &self.earth_model())
+    }
+
+    // in degrees of longitude (x) and latitude (y), None for reduced grids
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        if self.is_reduced() {
+            return None;
+        }
+
+        affine(|i, j| {
+            let (lat, lon) = self.latlon(i, j);
+            Some((lon, lat))
+        })
+    }
+}
+
+// Gaussian latitudes are not evenly spaced, so these grids have no geotransform
+impl GaussianGridDefinition {
+    pub fn proj_string(&self) -> String {
+        format!("+proj=longlat {} +no_defs", proj_earth(&self.earth_model()))
+    }
+
+    pub fn wkt(&self) -> String {
+        wkt_geographic("GRIB2 grid definition template 3.40", &self.earth_model())
+    }
+}
+
+impl RotatedLatLonGridDefinition {
+    pub fn proj_string(&self) -> String {
+        let (lat, lon) = self.southern_pole();
+
+        format!(
+            "+proj=ob_tran +o_proj=longlat +o_lon_p={} +o_lat_p={} +lon_0={} {} +no_defs",
+            0.0 - self.rotation_angle_degrees(), 0.0 - lat, signed_longitude(lon), proj_earth(&self.earth_model()))
+    }
+
+    // a geographic CRS derived by the GRIB convention of pole rotation, as written by PROJ and GDAL
+    pub fn wkt(&self) -> String {
+        let (lat, lon) = self.southern_pole();
+        let method = "Pole rotation (GRIB convention)";
+
+        format!(
+            "GEOGCRS[\"GRIB2 grid definition template 3.1\",BASEGEOGCRS[\"unnamed\",{}],\
+            DERIVINGCONVERSION[\"{}\",METHOD[\"{}\"],{},{},{}],{}]",
+            wkt_datum(&self.earth_model()),
+            method,
+            method,
+            wkt_parameter("Latitude of the southern pole (GRIB convention)", lat, DEGREE, None),
+            wkt_parameter("Longitude of the southern pole (GRIB convention)", signed_longitude(lon), DEGREE, None),
+            wkt_parameter("Axis rotation (GRIB convention)", self.rotation_angle_degrees(), DEGREE, None),
+            wkt_ellipsoidal_cs())
+    }
+
+    // in rotated degrees of longitude (x) and latitude (y), None for reduced grids
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        self.rotated_grid.geotransform()
+    }
+}
+
+impl MercatorGridDefinition {
+    pub fn proj_string(&self) -> String {
+        format!(
+            "+proj=merc +lat_ts={} +lon_0={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+            degrees(self.increments_latitude), signed_longitude(self.central_longitude()), proj_earth(&self.earth_model()))
+    }
+
+    pub fn wkt(&self) -> String {
+        let mut parameters = vec![
+            wkt_parameter("Latitude of 1st standard parallel", degrees(self.increments_latitude), DEGREE, Some(8823)),
+            wkt_parameter("Longitude of natural origin", signed_longitude(self.central_longitude()), DEGREE, Some(8802))
+        ];
+        parameters.extend(wkt_false_origin("False easting", 8806, "False northing", 8807));
+
+        wkt_projected("GRIB2 grid definition template 3.10", &self.earth_model(), "Mercator (variant B)", Some(9805), &parameters)
+    }
+
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        let projection = self.projection();
+        affine(|i, j| {
+            let (lat, lon) = self.latlon(i, j);
+            Some(projection.forward(lat, lon))
+        })
+    }
+}
+
+impl PolarStereographicGridDefinition {
+    pub fn proj_string(&self) -> String {
+        format!(
+            "+proj=stere +lat_0={} +lat_ts={} +lon_0={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+            if self.is_south_pole_centred() { -90 } else { 90 },
+            degrees(self.increments_latitude),
+            signed_longitude(degrees(self.orientation_longitude)),
+            proj_earth(&self.earth_model()))
+    }
+
+    pub fn wkt(&self) -> String {
+        let mut parameters = vec![
+            wkt_parameter("Latitude of standard parallel", degrees(self.increments_latitude), DEGREE, Some(8832)),
+            wkt_parameter("Longitude of origin", signed_longitude(degrees(self.orientation_longitude)), DEGREE, Some(8833))
+        ];
+        parameters.extend(wkt_false_origin("False easting", 8806, "False northing", 8807));
+
+        wkt_projected("GRIB2 grid definition template 3.20", &self.earth_model(), "Polar Stereographic (variant B)", Some(9829), &parameters)
+    }
+
+    // the plane coordinates of the projection are PROJ's, with the pole at the origin
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        let projection = self.projection();
+        affine(|i, j| {
+            let (lat, lon) = self.latlon(i, j);
+            Some(projection.forward(lat, lon))
+        })
+    }
+}
+
+impl LambertConformalGridDefinition {
+    // the origin of the CRS is put at LaD on LoV
+    pub fn proj_string(&self) -> String {
+        format!(
+            "+proj=lcc +lat_0={} +lon_0={} +lat_1={} +lat_2={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+            degrees(self.increments_latitude),
+            signed_longitude(degrees(self.orientation_longitude)),
+            degrees(self.first_secant_latitude),
+            degrees(self.second_secant_latitude),
+            proj_earth(&self.earth_model()))
+    }
+
+    pub fn wkt(&self) -> String {
+        let mut parameters = vec![
+            wkt_parameter("Latitude of false origin", degrees(self.increments_latitude), DEGREE, Some(8821)),
+            wkt_parameter("Longitude of false origin", signed_longitude(degrees(self.orientation_longitude)), DEGREE, Some(8822)),
+            wkt_parameter("Latitude of 1st standard parallel", degrees(self.first_secant_latitude), DEGREE, Some(8823)),
+            wkt_parameter("Latitude of 2nd standard parallel", degrees(self.second_secant_latitude), DEGREE, Some(8824))
+        ];
+        parameters.extend(wkt_false_origin("Easting at false origin", 8826, "Northing at false origin", 8827));
+
+        wkt_projected("GRIB2 grid definition template 3.30", &self.earth_model(), "Lambert Conic Conformal (2SP)", Some(9802), &parameters)
+    }
+
+    // the projection's plane has its origin at the apex of the cone, PROJ's at the false origin
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        let projection = self.projection();
+        let (x0, y0) = projection.forward(degrees(self.increments_latitude), degrees(self.orientation_longitude));
+
+        affine(|i, j| {
+            let (lat, lon) = self.latlon(i, j);
+            let (x, y) = projection.forward(lat, lon);
+            Some((x - x0, y - y0))
+        })
+    }
+}
+
+impl SpaceViewGridDefinition {
+    // the CGMS normalized geostationary projection scans along y, PROJ's default
+    pub fn proj_string(&self) -> String {
+        format!(
+            "+proj=geos +h={} +lon_0={} +sweep=y +x_0=0 +y_0=0 {} +units=m +no_defs",
+            self.altitude(), signed_longitude(self.sub_satellite_point().1), proj_earth(&self.earth_model()))
+    }
+
+    pub fn wkt(&self) -> String {
+        let mut parameters = vec![
+            wkt_parameter("Longitude of natural origin", signed_longitude(self.sub_satellite_point().1), DEGREE, Some(8802)),
+            wkt_parameter("Satellite Height", self.altitude(), METRE, None)
+        ];
+        parameters.extend(wkt_false_origin("False easting", 8806, "False northing", 8807));
+
+        wkt_projected("GRIB2 grid definition template 3.90", &self.earth_model(), "Geostationary Satellite (Sweep Y)", None, &parameters)
+    }
+
+    // PROJ's plane coordinates are the scan angles scaled by the height of the satellite
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        let altitude = self.altitude();
+        affine(|i, j| {
+            let (x, y) = self.scan_angles(i, j);
+            Some((x * altitude, y * altitude))
+        })
+    }
+}
+
+impl GridDefinition {
+    // None for the Arakawa staggered, unstructured and unsupported templates
+    pub fn proj_string(&self) -> Option<String> {
+        match self {
+            GridDefinition::LatLon(def) => Some(def.proj_string()),
+            GridDefinition::RotatedLatLon(def) => Some(def.proj_string()),
+            GridDefinition::Gaussian(def) => Some(def.proj_string()),
+            GridDefinition::Mercator(def) => Some(def.proj_string()),
+            GridDefinition::PolarStereographic(def) => Some(def.proj_string()),
+            GridDefinition::LambertConformal(def) => Some(def.proj_string()),
+            GridDefinition::SpaceView(def) => Some(def.proj_string()),
+            _ => None
+        }
+    }
+
+    pub fn wkt(&self) -> Option<String> {
+        match self {
+            GridDefinition::LatLon(def) => Some(def.wkt()),
+            GridDefinition::RotatedLatLon(def) => Some(def.wkt()),
+            GridDefinition::Gaussian(def) => Some(def.wkt()),
+            GridDefinition::Mercator(def) => Some(def.wkt()),
+            GridDefinition::PolarStereographic(def) => Some(def.wkt()),
+            GridDefinition::LambertConformal(def) => Some(def.wkt()),
+            GridDefinition::SpaceView(def) => Some(def.wkt()),
+            _ => None
+        }
+    }
+
+    // None for grids which are not regular in their CRS, Gaussian and reduced grids among them
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        match self {
+            GridDefinition::LatLon(def) => def.geotransform(),
+            GridDefinition::RotatedLatLon(def) => def.geotransform(),
+            GridDefinition::Mercator(def) => def.geotransform(),
+            GridDefinition::PolarStereographic(def) => def.geotransform(),
+            GridDefinition::LambertConformal(def) => def.geotransform(),
+            GridDefinition::SpaceView(def) => def.geotransform(),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,123 @@
+/*
+The shape of the earth a grid is defined on, decoded from code table 3.2.
+All lengths are in metres.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EarthModel {
+    // spherical earth with the given radius (shapes 0, 1, 6 and 8)
+    Sphere(f64),
+    // IAU 1965 oblate spheroid (shape 2)
+    Iau1965,
+    // IAG-GRS80 (shape 4)
+    Grs80,
+    // WGS84 (shape 5)
+    Wgs84,
+    // OSGB 1936, the Airy 1830 spheroid (shape 9)
+    Airy1830,
+    // oblate spheroid with axes given in the template (shapes 3 and 7)
+    Oblate {
+        semi_major_axis: f64,
+        semi_minor_axis: f64
+    }
+}
+
+fn scaled(factor: u8, value: i64) -> f64 {
+    value as f64 / 10f64.powi(factor as i32)
+}
+
+impl EarthModel {
+    /*
+    Decodes the shape of the earth and the six scale factor/value octets that follow it in every
+    grid template which carries them. Shapes which are missing or reserved fall back to the
+    6371229 m sphere used by most centres.
+    */
+    pub fn from_template(
+        shape: u8,
+        radius_scale_factor: u8,
+        radius_scale_value: i64,
+        major_axis_scale_factor: u8,
+        major_axis_scale_value: i64,
+        minor_axis_scale_factor: u8,
+        minor_axis_scale_value: i64) -> EarthModel {
+        let major_axis = scaled(major_axis_scale_factor, major_axis_scale_value);
+        let minor_axis = scaled(minor_axis_scale_factor, minor_axis_scale_value);
+
+        match shape {
+            0 => EarthModel::Sphere(6367470.0),
+            1 => EarthModel::Sphere(scaled(radius_scale_factor, radius_scale_value)),
+            2 => EarthModel::Iau1965,
+            // shape 3 gives the axes in kilometres, shape 7 in metres
+            3 => EarthModel::Oblate {
+                semi_major_axis: major_axis * 1000.0,
+                semi_minor_axis: minor_axis * 1000.0
+            },
+            4 => EarthModel::Grs80,
+            5 => EarthModel::Wgs84,
+            7 => EarthModel::Oblate {
+                semi_major_axis: major_axis,
+                semi_minor_axis: minor_axis
+            },
+            8 => EarthModel::Sphere(6371200.0),
+            9 => EarthModel::Airy1830,
+            _ => EarthModel::Sphere(6371229.0)
+        }
+    }
+
+    pub fn semi_major_axis(&self) -> f64 {
+        match self {
+            EarthModel::Sphere(radius) => *radius,
+            EarthModel::Iau1965 => 6378160.0,
+            EarthModel::Grs80 | EarthModel::Wgs84 => 6378137.0,
+            EarthModel::Airy1830 => 6377563.396,
+            EarthModel::Oblate { semi_major_axis, .. } => *semi_major_axis
+        }
+    }
+
+    pub fn semi_minor_axis(&self) -> f64 {
+        match self {
+            EarthModel::Sphere(radius) => *radius,
+            EarthModel::Iau1965 => 6356775.0,
+            EarthModel::Grs80 => 6356752.314140,
+            EarthModel::Wgs84 => 6356752.314245,
+            EarthModel::Airy1830 => 6356256.909,
+            EarthModel::Oblate { semi_minor_axis, .. } => *semi_minor_axis
+        }
+    }
+
+    pub fn is_sphere(&self) -> bool {
+        self.semi_major_axis() == self.semi_minor_axis()
+    }
+
+    pub fn flattening(&self) -> f64 {
+        1.0 - self.semi_minor_axis() / self.semi_major_axis()
+    }
+
+    pub fn eccentricity(&self) -> f64 {
+        let (a, b) = (self.semi_major_axis(), self.semi_minor_axis());
+        (1.0 - (b * b) / (a * a)).max(0.0).sqrt()
+    }
+
+    // semi-major axis and eccentricity, as used by the projections
+    pub(crate) fn ellipsoid(&self) -> (f64, f64) {
+        (self.semi_major_axis(), self.eccentricity())
+    }
+
+    // the IUGG mean radius, (2a + b) / 3
+    pub fn mean_radius(&self) -> f64 {
+        (2.0 * self.semi_major_axis() + self.semi_minor_axis()) / 3.0
+    }
+
+    /*
+    Great circle distance in metres between two points given in degrees, on a sphere of the
+    model's mean radius. Good to a few tenths of a percent on the oblate models.
+    */
+    pub fn distance(&self, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+        let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+        let dphi = phi2 - phi1;
+        let dlambda = (lon2 - lon1).to_radians();
+
+        let h = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+        2.0 * self.mean_radius() * h.sqrt().min(1.0).asin()
+    }
+}
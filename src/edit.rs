@@ -0,0 +1,213 @@
+use chrono::{Datelike, Timelike};
+
+use crate::{Grib2Error, Grib2Info, Grib2Message, product::HorizontalLayerProductDefinition, repack::template_length, sections::{self, Section, SectionIndex}};
+
+/*
+In-place editing of message metadata.
+
+Replacement sections are encoded by g2c into a throwaway message and spliced into the
+original, so the packed data in Sections 5 through 7 is never decoded or touched.
+Edits which would change the length of a section are refused unless `reencode` is set.
+*/
+
+fn section1_values(info: &Grib2Info) -> Vec<i64> {
+    vec![
+        info.center as i64,
+        info.subcenter as i64,
+        info.master_table_version as i64,
+        info.local_table_version as i64,
+        info.ref_time_significance as i64,
+        info.ref_time.year() as i64,
+        info.ref_time.month() as i64,
+        info.ref_time.day() as i64,
+        info.ref_time.hour() as i64,
+        info.ref_time.minute() as i64,
+        info.ref_time.second() as i64,
+        info.prod_status as i64,
+        info.data_type as i64
+    ]
+}
+
+unsafe fn encode_identification_section(info: &Grib2Info) -> Result<Vec<u8>, Grib2Error> {
+    let mut sec0: Vec<i64> = vec![info.discipline as i64, 2];
+    let mut sec1 = section1_values(info);
+    let mut buffer: Vec<u8> = vec![0; 1024];
+
+    let length = libg2c_sys::g2_create(buffer.as_mut_ptr(), sec0.as_mut_ptr(), sec1.as_mut_ptr());
+    if length < 0 {
+        return Err(Grib2Error::MessageEncodeError(length as i8));
+    }
+
+    // g2_create writes Section 0 followed directly by Section 1
+    Ok(buffer[16..length as usize].to_vec())
+}
+
+unsafe fn encode_product_section(template_number: u16, values: &[i64], coords: &[f32]) -> Result<Vec<u8>, Grib2Error> {
+    let mut sec0: Vec<i64> = vec![0, 2];
+    let mut sec1: Vec<i64> = vec![255, 0, 2, 0, 0, 1970, 1, 1, 0, 0, 0, 0, 0];
+
+    // a single point grid is enough to carry the product definition
+    let mut igds: Vec<i64> = vec![0, 1, 0, 0, 0];
+    let mut grid_template: Vec<i64> = vec![6, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 48, 0, 0, 0, 0, 0];
+
+    /*
+    g2c reads every value the template defines, extensions included, and the simple packing
+    of the single data value writes back into the data representation template, so both are
+    padded to the length g2c expects for their template number.
+    */
+    let prod_length = template_length(4, template_number, values).ok_or(Grib2Error::FieldEncodeError(-5))?;
+    let mut prod_template = values.to_vec();
+    prod_template.resize(prod_length.max(values.len()), 0);
+
+    let mut coord_list = coords.to_vec();
+    let mut drt: Vec<i64> = vec![0; template_length(5, 0, &[]).ok_or(Grib2Error::FieldEncodeError(-7))?];
+    let mut data: Vec<f32> = vec![0.0];
+
+    let mut buffer: Vec<u8> = vec![0; 4096 + 8 * (prod_template.len() + coord_list.len())];
+
+    let create_err = libg2c_sys::g2_create(buffer.as_mut_ptr(), sec0.as_mut_ptr(), sec1.as_mut_ptr());
+    if create_err < 0 {
+        return Err(Grib2Error::MessageEncodeError(create_err as i8));
+    }
+
+    let grid_err = libg2c_sys::g2_addgrid(
+        buffer.as_mut_ptr(),
+        igds.as_mut_ptr(),
+        grid_template.as_mut_ptr(),
+        std::ptr::null_mut(),
+        0);
+    if grid_err < 0 {
+        return Err(Grib2Error::GridEncodeError(grid_err as i8));
+    }
+
+    let field_err = libg2c_sys::g2_addfield(
+        buffer.as_mut_ptr(),
+        template_number as i64,
+        prod_template.as_mut_ptr(),
+        if coord_list.is_empty() { std::ptr::null_mut() } else { coord_list.as_mut_ptr() },
+        coord_list.len() as i64,
+        0,
+        drt.as_mut_ptr(),
+        data.as_mut_ptr(),
+        1,
+        255,
+        std::ptr::null_mut());
+    if field_err < 0 {
+        return Err(Grib2Error::FieldEncodeError(field_err as i8));
+    }
+
+    let length = libg2c_sys::g2_gribend(buffer.as_mut_ptr());
+    if length < 0 {
+        return Err(Grib2Error::MessageEncodeError(length as i8));
+    }
+
+    let index = SectionIndex::new(&buffer[..length as usize]).ok_or(Grib2Error::InvalidMessage)?;
+    Ok(buffer[index.section(4).ok_or(Grib2Error::InvalidMessage)?.range()].to_vec())
+}
+
+impl Grib2Message {
+    fn spliced(&self, section: Section, bytes: Vec<u8>, reencode: bool) -> Result<Vec<u8>, Grib2Error> {
+        if bytes.len() != section.length && !reencode {
+            return Err(Grib2Error::SectionLengthChanged(section.number));
+        }
+
+        let mut raw: Vec<u8> = Vec::with_capacity(self.bytes().len() + bytes.len());
+        raw.extend_from_slice(&self.bytes()[..section.offset]);
+        raw.extend_from_slice(&bytes);
+        raw.extend_from_slice(&self.bytes()[section.offset + section.length..]);
+
+        let total = raw.len();
+        sections::write_uint(&mut raw, 8, 8, total as u64);
+
+        Ok(raw)
+    }
+
+    // replaces this message with the edited bytes, leaving it untouched if they do not decode
+    fn reload(&mut self, raw: Vec<u8>) -> Result<(), Grib2Error> {
+        let mut edited = Grib2Message::from_bytes(raw)?;
+        if let Some((_, why)) = edited.errors().first() {
+            return Err(why.clone());
+        }
+
+        edited.inherit_predefined_bitmaps(self);
+
+        *self = edited;
+        Ok(())
+    }
+
+    /*
+    Rewrites Section 1 (and the discipline in Section 0) from the given info.
+    The message length and GRIB edition cannot be edited.
+    */
+    pub fn set_info(&mut self, info: &Grib2Info, reencode: bool) -> Result<(), Grib2Error> {
+        let current = self.info().map_err(|why| why.clone())?.clone();
+
+        if info.grib_edition != current.grib_edition {
+            return Err(Grib2Error::InvalidEdit(String::from("grib_edition")));
+        }
+
+        if info.length != current.length {
+            return Err(Grib2Error::InvalidEdit(String::from("length")));
+        }
+
+        let index = SectionIndex::new(self.bytes()).ok_or(Grib2Error::InvalidMessage)?;
+        let sec1 = *index.section(1).ok_or(Grib2Error::InvalidMessage)?;
+
+        let mut encoded = unsafe { encode_identification_section(info)? };
+
+        // keep any reserved octets past the end of the standard Section 1
+        if encoded.len() < sec1.length {
+            encoded.extend_from_slice(&self.bytes()[sec1.offset + encoded.len()..sec1.offset + sec1.length]);
+            sections::write_uint(&mut encoded, 0, 4, sec1.length as u64);
+        }
+
+        let mut raw = self.spliced(sec1, encoded, reencode)?;
+        raw[6] = info.discipline;
+
+        self.reload(raw)
+    }
+
+    /*
+    Replaces the product definition template of the given (zero based) field.
+    Changing the template number or its extensions generally changes the length of Section 4.
+    */
+    pub fn set_prod_template(&mut self, field: usize, template_number: u16, values: &[i64], reencode: bool) -> Result<(), Grib2Error> {
+        let current = self.fields()
+            .iter()
+            .find(|f| f.field_number as usize == field + 1)
+            .ok_or(Grib2Error::FieldReadError(6))?;
+
+        let index = SectionIndex::new(self.bytes()).ok_or(Grib2Error::InvalidMessage)?;
+        let sec4 = *index.field_section(field, 4).ok_or(Grib2Error::InvalidMessage)?;
+
+        // the optional list of vertical coordinate values sits at the end of Section 4
+        let coords_start = sec4.offset + sec4.length - 4 * current.num_coords as usize;
+        let coords: Vec<f32> = (0..current.num_coords as usize)
+            .map(|c| f32::from_bits(sections::read_uint(self.bytes(), coords_start + 4 * c, 4).unwrap() as u32))
+            .collect();
+
+        let encoded = unsafe { encode_product_section(template_number, values, &coords)? };
+        let raw = self.spliced(sec4, encoded, reencode)?;
+        self.reload(raw)
+    }
+
+    // Rewrites the values shared by the horizontal layer product templates (4.0 through 4.15).
+    pub fn set_product_definition(&mut self, field: usize, definition: &HorizontalLayerProductDefinition) -> Result<(), Grib2Error> {
+        let current = self.fields()
+            .iter()
+            .find(|f| f.field_number as usize == field + 1)
+            .ok_or(Grib2Error::FieldReadError(6))?;
+
+        let mut values = current.prod_template_values().clone();
+        let definition_values = definition.template_values();
+
+        if values.len() < definition_values.len() {
+            return Err(Grib2Error::InvalidEdit(String::from("prod_template")));
+        }
+
+        values.splice(0..definition_values.len(), definition_values);
+
+        let template_number = current.prod_codepoint;
+        self.set_prod_template(field, template_number, &values, false)
+    }
+}
@@ -1,5 +1,509 @@
 
+use std::{collections::HashMap, fs::File, hash::{Hash, Hasher}, io::{BufRead, BufReader}, path::Path, sync::{Arc, Mutex, OnceLock}};
+
+use crate::Grib2Error;
 use crate::codetables::Codetable;
+use crate::earth::EarthModel;
+use crate::projection::{Geostationary, LambertConic, Mercator, PolarStereographic, PoleRotation};
+
+/*
+The earth model of a grid definition, from the shape of the earth and the six scale factor and
+value fields which every template carrying them names alike.
+*/
+macro_rules! earth_model {
+    ($definition:expr) => {
+        EarthModel::from_template(
+            $definition.earth_shape,
+            $definition.earth_radius_scale_factor,
+            $definition.earth_radius_scale_value,
+            $definition.earth_oblate_spheroid_major_axis_scale_factor,
+            $definition.earth_oblate_spheroid_major_axis_scale_value,
+            $definition.earth_oblate_spheroid_minor_axis_scale_factor,
+            $definition.earth_oblate_spheroid_minor_axis_scale_value)
+    };
+}
+
+// angles in grid templates are stored in units of 10^-6 degrees
+const MICRO_DEGREES: f64 = 1e-6;
+// grid lengths in grid templates are stored in units of 10^-3 metres
+const MILLIMETRES: f64 = 1e-3;
+// the space view camera distance Nr is stored in units of 10^-6 equatorial radii
+const MICRO_RADII: f64 = 1e-6;
+// four octet template values with every bit set are missing
+const MISSING_U32: i64 = 0xFFFFFFFF;
+
+// degrees per unit of a template's angles, from its basic angle and subdivisions when both are given
+fn angle_unit(basic_angle: i64, subdivisions: i64) -> f64 {
+    if basic_angle == 0 || subdivisions == 0 || basic_angle == MISSING_U32 || subdivisions == MISSING_U32 {
+        MICRO_DEGREES
+    } else {
+        basic_angle as f64 / subdivisions as f64
+    }
+}
+
+// whether a grid lists the number of points in each row, leaving Ni missing
+fn is_reduced(parallel_point_count: i64, list_point_counts: &[i64]) -> bool {
+    (parallel_point_count == MISSING_U32 || parallel_point_count == 0) && !list_point_counts.is_empty()
+}
+
+// +1.0 or -1.0 for the direction the i and j indices step through the grid (code table 3.4)
+fn scan_directions(scanning_mode: u8) -> (f64, f64) {
+    (
+        if scanning_mode & 0x80 == 0 { 1.0 } else { -1.0 },
+        if scanning_mode & 0x40 == 0 { -1.0 } else { 1.0 }
+    )
+}
+
+/*
+Signed longitude step between the points of a row with `count` points, running from the first
+to the last longitude of the grid. Rows going all the way around the globe stop one increment
+short of the first longitude, either their own increment or that of the widest row.
+*/
+fn row_increment(scanning_mode: u8, first_lon: f64, last_lon: f64, count: usize, widest: usize) -> f64 {
+    let (di, _) = scan_directions(scanning_mode);
+    let span = (di * (last_lon - first_lon)).rem_euclid(360.0);
+
+    let wraps = |points: usize| points > 0 && (span + 360.0 / points as f64 - 360.0).abs() < 1e-3;
+
+    if span == 0.0 || wraps(count) || wraps(widest) {
+        di * 360.0 / count.max(1) as f64
+    } else if count > 1 {
+        di * span / (count - 1) as f64
+    } else {
+        0.0
+    }
+}
+
+/*
+The scanning mode flags of a grid (code table 3.4).
+Points of a grid are addressed by (i, j) indices counted from the first grid point; the flags
+say which way those indices run and in which order the points are stored.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ScanningMode {
+    // bit 1, points along i run west (or towards -x) instead of east
+    pub i_negative: bool,
+    // bit 2, points along j run north (or towards +y) instead of south
+    pub j_positive: bool,
+    // bit 3, points along j are consecutive instead of points along i
+    pub column_major: bool,
+    // bit 4, every other row (or column) runs in the opposite direction
+    pub boustrophedon: bool
+}
+
+impl ScanningMode {
+    pub fn from_flags(flags: u8) -> ScanningMode {
+        ScanningMode {
+            i_negative: flags & 0x80 != 0,
+            j_positive: flags & 0x40 != 0,
+            column_major: flags & 0x20 != 0,
+            boustrophedon: flags & 0x10 != 0
+        }
+    }
+
+    pub fn to_flags(&self) -> u8 {
+        (self.i_negative as u8) << 7 | (self.j_positive as u8) << 6 | (self.column_major as u8) << 5 | (self.boustrophedon as u8) << 4
+    }
+
+    // north to south rows of west to east points, one row after the other
+    pub fn is_canonical(&self) -> bool {
+        *self == ScanningMode::default()
+    }
+
+    /*
+    The (i, j) indices of the points of an ni by nj grid, in the order they are stored.
+    Points run along i first unless columns come first, and every other row (or column) is
+    reversed on boustrophedon grids.
+    */
+    pub fn point_order(&self, ni: usize, nj: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (column_major, boustrophedon) = (self.column_major, self.boustrophedon);
+        let (outer, inner) = if column_major { (ni, nj) } else { (nj, ni) };
+
+        (0..outer).flat_map(move |line| {
+            (0..inner).map(move |step| {
+                let step = if boustrophedon && line % 2 == 1 { inner - 1 - step } else { step };
+                if column_major { (line, step) } else { (step, line) }
+            })
+        })
+    }
+
+    // the position of point (i, j) in the stored order, the inverse of point_order()
+    pub fn storage_index(&self, i: usize, j: usize, ni: usize, nj: usize) -> usize {
+        let (line, step, inner) = if self.column_major { (i, j, nj) } else { (j, i, ni) };
+        let step = if self.boustrophedon && line % 2 == 1 { inner - 1 - step } else { step };
+
+        line * inner + step
+    }
+
+    // the (i, j) indices of the point at a position in the stored order, the inverse of storage_index()
+    pub fn grid_index(&self, index: usize, ni: usize, nj: usize) -> (usize, usize) {
+        let inner = if self.column_major { nj } else { ni }.max(1);
+        let (line, step) = (index / inner, index % inner);
+        let step = if self.boustrophedon && line % 2 == 1 { inner - 1 - step } else { step };
+
+        if self.column_major { (line, step) } else { (step, line) }
+    }
+
+    // where point (i, j) lands in the canonical layout
+    fn canonical_index(&self, i: usize, j: usize, ni: usize, nj: usize) -> usize {
+        let column = if self.i_negative { ni - 1 - i } else { i };
+        let row = if self.j_positive { nj - 1 - j } else { j };
+
+        row * ni + column
+    }
+
+    /*
+    Reorders values stored in this scanning mode into the canonical layout: rows from north to
+    south (or +y to -y), points from west to east (or -x to +x), row after row.
+    None when the number of values does not match the grid.
+    */
+    pub fn to_canonical<T: Copy>(&self, values: &[T], ni: usize, nj: usize) -> Option<Vec<T>> {
+        if values.len() != ni * nj {
+            return None;
+        }
+
+        let mut canonical = values.to_vec();
+        for (value, (i, j)) in values.iter().zip(self.point_order(ni, nj)) {
+            canonical[self.canonical_index(i, j, ni, nj)] = *value;
+        }
+
+        Some(canonical)
+    }
+
+    // the inverse of to_canonical(), putting canonically ordered values back into this scanning mode
+    pub fn from_canonical<T: Copy>(&self, values: &[T], ni: usize, nj: usize) -> Option<Vec<T>> {
+        if values.len() != ni * nj {
+            return None;
+        }
+
+        Some(
+            self.point_order(ni, nj)
+                .map(|(i, j)| values[self.canonical_index(i, j, ni, nj)])
+                .collect()
+        )
+    }
+}
+
+/*
+The resolution and component flags of a grid (code table 3.3).
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ResolutionFlags {
+    // bit 3, the i direction increment is given
+    pub i_increment_given: bool,
+    // bit 4, the j direction increment is given
+    pub j_increment_given: bool,
+    // bit 5, vector components are resolved along the x and y axes of the grid instead of east and north
+    pub grid_relative_vectors: bool
+}
+
+impl ResolutionFlags {
+    pub fn from_flags(flags: u8) -> ResolutionFlags {
+        ResolutionFlags {
+            i_increment_given: flags & 0x20 != 0,
+            j_increment_given: flags & 0x10 != 0,
+            grid_relative_vectors: flags & 0x08 != 0
+        }
+    }
+
+    pub fn to_flags(&self) -> u8 {
+        (self.i_increment_given as u8) << 5 | (self.j_increment_given as u8) << 4 | (self.grid_relative_vectors as u8) << 3
+    }
+}
+
+/*
+Latitudes and longitudes in degrees of the points of a grid.
+Regular lat/lon and Gaussian grids are described by their axes, other grids by the position
+of every point in the order the points are stored, with NaN for points not on the earth.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridCoordinates {
+    // latitudes along j and longitudes along i, in the scanning directions
+    Axes {
+        latitudes: Vec<f64>,
+        longitudes: Vec<f64>
+    },
+    // one latitude and longitude per point; the shape is (Ni, Nj) for grids that have one
+    Points {
+        shape: Option<(usize, usize)>,
+        latitudes: Vec<f64>,
+        longitudes: Vec<f64>
+    }
+}
+
+impl GridCoordinates {
+    fn from_points(shape: Option<(usize, usize)>, points: Vec<(f64, f64)>) -> GridCoordinates {
+        let (latitudes, longitudes) = points.into_iter().unzip();
+
+        GridCoordinates::Points {
+            shape,
+            latitudes,
+            longitudes
+        }
+    }
+
+    // projects the grid's latlon() over every point in storage order
+    fn from_grid<F: Fn(f64, f64) -> (f64, f64)>(scanning_mode: u8, shape: (usize, usize), latlon: F) -> GridCoordinates {
+        let points = ScanningMode::from_flags(scanning_mode)
+            .point_order(shape.0, shape.1)
+            .map(|(i, j)| latlon(i as f64, j as f64))
+            .collect();
+
+        GridCoordinates::from_points(Some(shape), points)
+    }
+
+    // the number of grid points covered
+    pub fn len(&self) -> usize {
+        match self {
+            GridCoordinates::Axes { latitudes, longitudes } => latitudes.len() * longitudes.len(),
+            GridCoordinates::Points { latitudes, .. } => latitudes.len()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/*
+A grid definition, picked by the grid definition template number (code table 3.1).
+Templates which g2r cannot interpret yet keep their raw template values.
+*/
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub enum GridDefinition {
+    LatLon(LatLonGridDefinition),
+    RotatedLatLon(RotatedLatLonGridDefinition),
+    Gaussian(GaussianGridDefinition),
+    Mercator(MercatorGridDefinition),
+    PolarStereographic(PolarStereographicGridDefinition),
+    LambertConformal(LambertConformalGridDefinition),
+    SpaceView(SpaceViewGridDefinition),
+    Unstructured(UnstructuredGridDefinition),
+    ArakawaRotatedLatLon(ArakawaRotatedLatLonGridDefinition),
+    Unsupported {
+        number: u32,
+        raw: Vec<i64>
+    }
+}
+
+impl GridDefinition {
+    pub fn new(number: u32, grid_template: &Vec<i64>) -> GridDefinition {
+        let definition = match number {
+            0 => LatLonGridDefinition::new(grid_template).map(GridDefinition::LatLon),
+            1 => RotatedLatLonGridDefinition::new(grid_template).map(GridDefinition::RotatedLatLon),
+            10 => MercatorGridDefinition::new(grid_template).map(GridDefinition::Mercator),
+            20 => PolarStereographicGridDefinition::new(grid_template).map(GridDefinition::PolarStereographic),
+            30 => LambertConformalGridDefinition::new(grid_template).map(GridDefinition::LambertConformal),
+            40 => GaussianGridDefinition::new(grid_template).map(GridDefinition::Gaussian),
+            90 => SpaceViewGridDefinition::new(grid_template).map(GridDefinition::SpaceView),
+            101 => UnstructuredGridDefinition::new(grid_template).map(GridDefinition::Unstructured),
+            32768 => ArakawaRotatedLatLonGridDefinition::new(ArakawaStaggering::E, grid_template)
+                .map(GridDefinition::ArakawaRotatedLatLon),
+            32769 => ArakawaRotatedLatLonGridDefinition::new(ArakawaStaggering::B, grid_template)
+                .map(GridDefinition::ArakawaRotatedLatLon),
+            _ => None
+        };
+
+        definition.unwrap_or_else(|| GridDefinition::Unsupported {
+            number,
+            raw: grid_template.clone()
+        })
+    }
+
+    pub fn template_number(&self) -> u32 {
+        match self {
+            GridDefinition::LatLon(_) => 0,
+            GridDefinition::RotatedLatLon(_) => 1,
+            GridDefinition::Mercator(_) => 10,
+            GridDefinition::PolarStereographic(_) => 20,
+            GridDefinition::LambertConformal(_) => 30,
+            GridDefinition::Gaussian(_) => 40,
+            GridDefinition::SpaceView(_) => 90,
+            GridDefinition::Unstructured(_) => 101,
+            GridDefinition::ArakawaRotatedLatLon(def) => match def.staggering {
+                ArakawaStaggering::E => 32768,
+                ArakawaStaggering::B => 32769
+            },
+            GridDefinition::Unsupported { number, .. } => *number
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, GridDefinition::Unsupported { .. })
+    }
+
+    /*
+    Latitude and longitude in degrees of grid point (i, j). On reduced grids i counts the points
+    of row j. None for unstructured and unsupported grids, and for points off the earth.
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> Option<(f64, f64)> {
+        match self {
+            GridDefinition::LatLon(def) if def.is_reduced() => {
+                let row = j.round().max(0.0) as usize;
+                Some((def.latlon(0.0, j).0, def.first_gridpoint().1 + i * def.row_increment(row)))
+            },
+            GridDefinition::LatLon(def) => Some(def.latlon(i, j)),
+            GridDefinition::RotatedLatLon(def) => Some(def.latlon(i, j)),
+            GridDefinition::Gaussian(def) => Some(def.latlon(i, j)),
+            GridDefinition::Mercator(def) => Some(def.latlon(i, j)),
+            GridDefinition::PolarStereographic(def) => Some(def.latlon(i, j)),
+            GridDefinition::LambertConformal(def) => Some(def.latlon(i, j)),
+            GridDefinition::SpaceView(def) => def.latlon(i, j),
+            GridDefinition::ArakawaRotatedLatLon(def) => Some(def.latlon(i, j)),
+            GridDefinition::Unstructured(_) | GridDefinition::Unsupported { .. } => None
+        }
+    }
+
+    /*
+    The inverse of latlon(), giving fractional grid indices. On reduced grids i is counted
+    along the row nearest to the latitude.
+    */
+    pub fn grid_index(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        match self {
+            GridDefinition::LatLon(def) if def.is_reduced() => {
+                let (_, j) = def.grid_index(lat, lon);
+                let row = j.round().max(0.0).min((def.meridional_point_count - 1).max(0) as f64) as usize;
+                let increment = def.row_increment(row);
+
+                let i = if increment == 0.0 {
+                    0.0
+                } else {
+                    (increment.signum() * (lon - def.first_gridpoint().1)).rem_euclid(360.0) / increment.abs()
+                };
+
+                Some((i, j))
+            },
+            GridDefinition::LatLon(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::RotatedLatLon(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::Gaussian(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::Mercator(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::PolarStereographic(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::LambertConformal(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::SpaceView(def) => def.grid_index(lat, lon),
+            GridDefinition::ArakawaRotatedLatLon(def) => Some(def.grid_index(lat, lon)),
+            GridDefinition::Unstructured(_) | GridDefinition::Unsupported { .. } => None
+        }
+    }
+
+    // the number of points in each row, for reduced and regular grids alike
+    pub fn row_point_counts(&self) -> Option<Vec<usize>> {
+        match (self, self.grid_shape()) {
+            (_, Some((ni, nj))) => Some(vec![ni; nj]),
+            (GridDefinition::LatLon(def), None) => Some((0..def.meridional_point_count.max(0) as usize).map(|row| def.row_point_count(row)).collect()),
+            (GridDefinition::Gaussian(def), None) => Some((0..def.row_count()).map(|row| def.row_point_count(row)).collect()),
+            _ => None
+        }
+    }
+
+    /*
+    Whether the rows of the grid go all the way around the globe, so that the last point of a
+    row neighbours the first. Only lat/lon and Gaussian grids, plain or rotated, can wrap.
+    */
+    pub fn wraps_longitude(&self) -> bool {
+        let (count, increment) = match self {
+            GridDefinition::LatLon(def) => (def.row_point_count(0), def.row_increment(0)),
+            GridDefinition::RotatedLatLon(def) => (def.rotated_grid.row_point_count(0), def.rotated_grid.row_increment(0)),
+            GridDefinition::Gaussian(def) => (def.row_point_count(0), def.row_increment(0)),
+            _ => return false
+        };
+
+        (count as f64 * increment.abs() - 360.0).abs() < 1e-3
+    }
+
+    pub fn earth_model(&self) -> Option<EarthModel> {
+        match self {
+            GridDefinition::LatLon(def) => Some(def.earth_model()),
+            GridDefinition::RotatedLatLon(def) => Some(def.earth_model()),
+            GridDefinition::Gaussian(def) => Some(def.earth_model()),
+            GridDefinition::Mercator(def) => Some(def.earth_model()),
+            GridDefinition::PolarStereographic(def) => Some(def.earth_model()),
+            GridDefinition::LambertConformal(def) => Some(def.earth_model()),
+            GridDefinition::SpaceView(def) => Some(def.earth_model()),
+            GridDefinition::Unstructured(def) => Some(def.earth_model()),
+            GridDefinition::ArakawaRotatedLatLon(def) => Some(def.earth_model()),
+            GridDefinition::Unsupported { .. } => None
+        }
+    }
+
+    pub fn scanning_mode(&self) -> Option<ScanningMode> {
+        let flags = match self {
+            GridDefinition::LatLon(def) => def.scanning_mode,
+            GridDefinition::RotatedLatLon(def) => def.rotated_grid.scanning_mode,
+            GridDefinition::Gaussian(def) => def.scanning_mode,
+            GridDefinition::Mercator(def) => def.scanning_mode,
+            GridDefinition::PolarStereographic(def) => def.scanning_mode,
+            GridDefinition::LambertConformal(def) => def.scanning_mode,
+            GridDefinition::SpaceView(def) => def.scanning_mode,
+            GridDefinition::ArakawaRotatedLatLon(def) => def.scanning_mode,
+            GridDefinition::Unstructured(_) | GridDefinition::Unsupported { .. } => return None
+        };
+
+        Some(ScanningMode::from_flags(flags))
+    }
+
+    pub fn resolution_flags(&self) -> Option<ResolutionFlags> {
+        let flags = match self {
+            GridDefinition::LatLon(def) => def.resolution_component_flags,
+            GridDefinition::RotatedLatLon(def) => def.rotated_grid.resolution_component_flags,
+            GridDefinition::Gaussian(def) => def.resolution_component_flags,
+            GridDefinition::Mercator(def) => def.resolution_component_flags,
+            GridDefinition::PolarStereographic(def) => def.resolution_component_flags,
+            GridDefinition::LambertConformal(def) => def.resolution_component_flags,
+            GridDefinition::SpaceView(def) => def.resolution_component_flags,
+            GridDefinition::ArakawaRotatedLatLon(def) => def.resolution_component_flags,
+            GridDefinition::Unstructured(_) | GridDefinition::Unsupported { .. } => return None
+        };
+
+        Some(ResolutionFlags::from_flags(flags))
+    }
+
+    // (Ni, Nj) for grids with the same number of points in every row
+    pub fn grid_shape(&self) -> Option<(usize, usize)> {
+        match self {
+            GridDefinition::LatLon(def) if !def.is_reduced() => Some(def.grid_shape()),
+            GridDefinition::RotatedLatLon(def) if !def.rotated_grid.is_reduced() => Some(def.grid_shape()),
+            GridDefinition::Gaussian(def) if !def.is_reduced() => Some((def.row_point_count(0), def.row_count())),
+            GridDefinition::Mercator(def) => Some(def.grid_shape()),
+            GridDefinition::PolarStereographic(def) => Some(def.grid_shape()),
+            GridDefinition::LambertConformal(def) => Some(def.grid_shape()),
+            GridDefinition::SpaceView(def) => Some(def.grid_shape()),
+            GridDefinition::ArakawaRotatedLatLon(def) => Some(def.grid_shape()),
+            _ => None
+        }
+    }
+
+    /*
+    The coordinates of every point of the grid. Unstructured grids carry no coordinates of their
+    own (see Grib2Field::coordinates()), and unsupported templates give None.
+    */
+    pub fn coordinates(&self) -> Option<GridCoordinates> {
+        match self {
+            GridDefinition::LatLon(def) if def.is_reduced() => Some(GridCoordinates::from_points(None, def.coordinates())),
+            GridDefinition::LatLon(def) => Some(GridCoordinates::Axes {
+                latitudes: def.latitudes(),
+                longitudes: def.longitudes()
+            }),
+            GridDefinition::Gaussian(def) if def.is_reduced() => Some(GridCoordinates::from_points(None, def.coordinates())),
+            GridDefinition::Gaussian(def) => Some(GridCoordinates::Axes {
+                latitudes: def.latitudes(),
+                longitudes: def.longitudes()
+            }),
+            GridDefinition::RotatedLatLon(def) => Some(GridCoordinates::from_grid(
+                def.rotated_grid.scanning_mode, def.grid_shape(), |i, j| def.latlon(i, j))),
+            GridDefinition::ArakawaRotatedLatLon(def) => Some(GridCoordinates::from_grid(
+                def.scanning_mode, def.grid_shape(), |i, j| def.latlon(i, j))),
+            GridDefinition::Mercator(def) => Some(GridCoordinates::from_grid(
+                def.scanning_mode, def.grid_shape(), |i, j| def.latlon(i, j))),
+            GridDefinition::PolarStereographic(def) => Some(GridCoordinates::from_grid(
+                def.scanning_mode, def.grid_shape(), |i, j| def.latlon(i, j))),
+            GridDefinition::LambertConformal(def) => Some(GridCoordinates::from_grid(
+                def.scanning_mode, def.grid_shape(), |i, j| def.latlon(i, j))),
+            GridDefinition::SpaceView(def) => Some(GridCoordinates::from_grid(
+                def.scanning_mode, def.grid_shape(), |i, j| def.latlon(i, j).unwrap_or((f64::NAN, f64::NAN)))),
+            GridDefinition::Unstructured(_) | GridDefinition::Unsupported { .. } => None
+        }
+    }
+}
 
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct LatLonGridDefinition {
@@ -58,6 +562,10 @@ impl LatLonGridDefinition {
         )
     }
 
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
     pub fn lat_span(&self) -> (i64, i64) {
         (self.first_gridpoint_latitude, self.last_gridpoint_latitude)
     }
@@ -65,4 +573,1326 @@ impl LatLonGridDefinition {
     pub fn lon_span(&self) -> (i64, i64) {
         (self.first_gridpoint_longitude, self.last_gridpoint_longitude)
     }
+
+    // degrees per unit of the template's angles, 10^-6 unless a basic angle is given
+    pub fn angle_unit(&self) -> f64 {
+        angle_unit(self.init_production_domain_basic_angle, self.basic_angle_subdivisions)
+    }
+
+    pub fn first_gridpoint(&self) -> (f64, f64) {
+        (
+            self.first_gridpoint_latitude as f64 * self.angle_unit(),
+            self.first_gridpoint_longitude as f64 * self.angle_unit()
+        )
+    }
+
+    pub fn last_gridpoint(&self) -> (f64, f64) {
+        (
+            self.last_gridpoint_latitude as f64 * self.angle_unit(),
+            self.last_gridpoint_longitude as f64 * self.angle_unit()
+        )
+    }
+
+    /*
+    Signed steps in degrees between neighbouring points along i and j, following the scanning mode.
+    Increments flagged as absent in the resolution flags (code table 3.3), or missing, are
+    worked out from the first and last grid points instead.
+    */
+    pub fn increments(&self) -> (f64, f64) {
+        let (di, dj) = scan_directions(self.scanning_mode);
+        let (first, last) = (self.first_gridpoint(), self.last_gridpoint());
+
+        let i_increment = if self.resolution_component_flags & 0x20 != 0 && self.direction_increment_i != MISSING_U32 {
+            di * self.direction_increment_i as f64 * self.angle_unit()
+        } else if self.parallel_point_count > 1 && self.parallel_point_count != MISSING_U32 {
+            let span = (di * (last.1 - first.1)).rem_euclid(360.0);
+            di * if span == 0.0 { 360.0 } else { span } / (self.parallel_point_count - 1) as f64
+        } else {
+            0.0
+        };
+
+        let j_increment = if self.resolution_component_flags & 0x10 != 0 && self.direction_increment_j != MISSING_U32 {
+            dj * self.direction_increment_j as f64 * self.angle_unit()
+        } else if self.meridional_point_count > 1 {
+            (last.0 - first.0) / (self.meridional_point_count - 1) as f64
+        } else {
+            0.0
+        };
+
+        (i_increment, j_increment)
+    }
+
+    // reduced grids leave Ni missing and give the number of points in each row instead
+    pub fn is_reduced(&self) -> bool {
+        is_reduced(self.parallel_point_count, &self.list_point_counts)
+    }
+
+    pub fn row_point_count(&self, row: usize) -> usize {
+        if self.is_reduced() {
+            self.list_point_counts.get(row).map_or(0, |count| *count as usize)
+        } else {
+            self.parallel_point_count as usize
+        }
+    }
+
+    // signed longitude step between neighbouring points of the given row
+    pub fn row_increment(&self, row: usize) -> f64 {
+        if !self.is_reduced() {
+            return self.increments().0;
+        }
+
+        let widest = self.list_point_counts.iter().copied().max().unwrap_or(0);
+        row_increment(self.scanning_mode, self.first_gridpoint().1, self.last_gridpoint().1, self.row_point_count(row), widest as usize)
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        (self.parallel_point_count as usize, self.meridional_point_count as usize)
+    }
+
+    // latitudes of the rows in scanning order
+    pub fn latitudes(&self) -> Vec<f64> {
+        let (lat, _) = self.first_gridpoint();
+        let (_, dj) = self.increments();
+
+        (0..self.meridional_point_count.max(0)).map(|j| lat + j as f64 * dj).collect()
+    }
+
+    // longitudes of the columns in scanning order, empty for reduced grids
+    pub fn longitudes(&self) -> Vec<f64> {
+        if self.is_reduced() {
+            return Vec::new();
+        }
+
+        let (_, lon) = self.first_gridpoint();
+        let (di, _) = self.increments();
+
+        (0..self.parallel_point_count.max(0)).map(|i| lon + i as f64 * di).collect()
+    }
+
+    // the latitude and longitude of every point in the order the points are stored
+    pub fn coordinates(&self) -> Vec<(f64, f64)> {
+        let (_, lon) = self.first_gridpoint();
+
+        if self.is_reduced() {
+            return self.latitudes()
+                .iter()
+                .enumerate()
+                .flat_map(|(row, lat)| {
+                    let increment = self.row_increment(row);
+                    (0..self.row_point_count(row)).map(move |i| (*lat, lon + i as f64 * increment))
+                })
+                .collect();
+        }
+
+        let (ni, nj) = self.grid_shape();
+        ScanningMode::from_flags(self.scanning_mode).point_order(ni, nj).map(|(i, j)| self.latlon(i as f64, j as f64)).collect()
+    }
+
+    /*
+    Latitude and longitude in degrees of grid point (i, j), counted from the first grid point
+    in the scanning directions. Longitudes follow the grid's own convention, e.g. 0 to 360 for GFS.
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let (lat, lon) = self.first_gridpoint();
+        let (di, dj) = self.increments();
+
+        (lat + j * dj, lon + i * di)
+    }
+
+    // the inverse of latlon(), longitudes are taken modulo 360 degrees
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (lat0, lon0) = self.first_gridpoint();
+        let (di, dj) = self.increments();
+
+        (
+            if di == 0.0 { 0.0 } else { (di.signum() * (lon - lon0)).rem_euclid(360.0) / di.abs() },
+            if dj == 0.0 { 0.0 } else { (lat - lat0) / dj }
+        )
+    }
+}
+
+/*
+The latitudes of a global Gaussian grid with n parallels between a pole and the equator,
+from north to south. These are the roots of the Legendre polynomial of degree 2n.
+*/
+pub fn gaussian_latitudes(n: usize) -> Vec<f64> {
+    shared_gaussian_latitudes(n).as_ref().clone()
+}
+
+/*
+Finding the roots takes tens of milliseconds on the finest grids, so each set is worked out
+once and shared by every grid definition of the same N, for the life of the process.
+*/
+fn shared_gaussian_latitudes(n: usize) -> Arc<Vec<f64>> {
+    static SETS: OnceLock<Mutex<HashMap<usize, Arc<Vec<f64>>>>> = OnceLock::new();
+
+    let mut sets = SETS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Arc::clone(sets.entry(n).or_insert_with(|| Arc::new(legendre_roots(n))))
+}
+
+fn legendre_roots(n: usize) -> Vec<f64> {
+    let degree = 2 * n;
+    let mut north: Vec<f64> = Vec::with_capacity(n);
+
+    for root in 0..n {
+        // Newton's method from the usual first guess for the root
+        let mut z = (std::f64::consts::PI * (root as f64 + 0.75) / (degree as f64 + 0.5)).cos();
+
+        for _ in 0..100 {
+            let (mut p1, mut p2) = (1.0, 0.0);
+
+            for k in 1..=degree {
+                let p3 = p2;
+                p2 = p1;
+                p1 = ((2 * k - 1) as f64 * z * p2 - (k - 1) as f64 * p3) / k as f64;
+            }
+
+            let derivative = degree as f64 * (z * p1 - p2) / (z * z - 1.0);
+            let previous = z;
+            z -= p1 / derivative;
+
+            if (z - previous).abs() < 1e-15 {
+                break;
+            }
+        }
+
+        north.push(z.asin().to_degrees());
+    }
+
+    let mut latitudes = north.clone();
+    latitudes.extend(north.iter().rev().map(|lat| -lat));
+    latitudes
+}
+
+/*
+The latitudes of the rows of a Gaussian grid, picked out of the full set the first time they
+are needed. The cache takes no part in comparing or hashing definitions.
+*/
+#[derive(Clone, Default)]
+struct RowLatitudes(OnceLock<Vec<f64>>);
+
+impl PartialEq for RowLatitudes {
+    fn eq(&self, _: &RowLatitudes) -> bool {
+        true
+    }
+}
+
+impl Eq for RowLatitudes {}
+
+impl Hash for RowLatitudes {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+// grid definition template 3.40, regular and reduced (quasi-regular) Gaussian grids
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct GaussianGridDefinition {
+    pub earth_shape: u8,
+    pub earth_radius_scale_factor: u8,
+    pub earth_radius_scale_value: i64,
+    pub earth_oblate_spheroid_major_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_major_axis_scale_value: i64,
+    pub earth_oblate_spheroid_minor_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_minor_axis_scale_value: i64,
+    pub parallel_point_count: i64,
+    pub meridional_point_count: i64,
+    pub init_production_domain_basic_angle: i64,
+    pub basic_angle_subdivisions: i64,
+    pub first_gridpoint_latitude: i64,
+    pub first_gridpoint_longitude: i64,
+    pub resolution_component_flags: u8,
+    pub last_gridpoint_latitude: i64,
+    pub last_gridpoint_longitude: i64,
+    pub direction_increment_i: i64,
+    pub parallels_between_pole_and_equator: i64,
+    pub scanning_mode: u8,
+    pub list_point_counts: Vec<i64>,
+    row_latitudes: RowLatitudes
+}
+
+impl GaussianGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<GaussianGridDefinition> {
+        Some(GaussianGridDefinition {
+            earth_shape: *grid_template.first()? as u8,
+            earth_radius_scale_factor: *grid_template.get(1)? as u8,
+            earth_radius_scale_value: *grid_template.get(2)?,
+            earth_oblate_spheroid_major_axis_scale_factor: *grid_template.get(3)? as u8,
+            earth_oblate_spheroid_major_axis_scale_value: *grid_template.get(4)?,
+            earth_oblate_spheroid_minor_axis_scale_factor: *grid_template.get(5)? as u8,
+            earth_oblate_spheroid_minor_axis_scale_value: *grid_template.get(6)?,
+            parallel_point_count: *grid_template.get(7)?,
+            meridional_point_count: *grid_template.get(8)?,
+            init_production_domain_basic_angle: *grid_template.get(9)?,
+            basic_angle_subdivisions: *grid_template.get(10)?,
+            first_gridpoint_latitude: *grid_template.get(11)?,
+            first_gridpoint_longitude: *grid_template.get(12)?,
+            resolution_component_flags: *grid_template.get(13)? as u8,
+            last_gridpoint_latitude: *grid_template.get(14)?,
+            last_gridpoint_longitude: *grid_template.get(15)?,
+            direction_increment_i: *grid_template.get(16)?,
+            parallels_between_pole_and_equator: *grid_template.get(17)?,
+            scanning_mode: *grid_template.get(18)? as u8,
+            list_point_counts: (19..grid_template.len()).map(|exnn| *grid_template.get(exnn).unwrap()).collect(),
+            row_latitudes: RowLatitudes::default()
+        })
+    }
+
+    pub fn earth_shape(&self, code_table_3_2: &Codetable) -> Option<String> {
+        code_table_3_2.codepoint_lookup(
+            self.earth_shape as i64,
+            code_table_3_2.find_parameter("Meaning")?
+        )
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
+    // degrees per unit of the template's angles, 10^-6 unless a basic angle is given
+    pub fn angle_unit(&self) -> f64 {
+        angle_unit(self.init_production_domain_basic_angle, self.basic_angle_subdivisions)
+    }
+
+    pub fn first_gridpoint(&self) -> (f64, f64) {
+        (
+            self.first_gridpoint_latitude as f64 * self.angle_unit(),
+            self.first_gridpoint_longitude as f64 * self.angle_unit()
+        )
+    }
+
+    pub fn last_gridpoint(&self) -> (f64, f64) {
+        (
+            self.last_gridpoint_latitude as f64 * self.angle_unit(),
+            self.last_gridpoint_longitude as f64 * self.angle_unit()
+        )
+    }
+
+    // reduced grids leave Ni missing and give the number of points in each row instead
+    pub fn is_reduced(&self) -> bool {
+        is_reduced(self.parallel_point_count, &self.list_point_counts)
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.meridional_point_count as usize
+    }
+
+    pub fn row_point_count(&self, row: usize) -> usize {
+        if self.is_reduced() {
+            self.list_point_counts.get(row).map_or(0, |count| *count as usize)
+        } else {
+            self.parallel_point_count as usize
+        }
+    }
+
+    pub fn point_count(&self) -> usize {
+        (0..self.row_count()).map(|row| self.row_point_count(row)).sum()
+    }
+
+    /*
+    The latitudes of the rows of this grid in scanning order. The rows are picked out of the
+    full set of Gaussian latitudes starting from the one closest to the first grid point.
+    */
+    pub fn latitudes(&self) -> Vec<f64> {
+        self.row_latitudes().to_vec()
+    }
+
+    // as latitudes(), worked out on the first call and kept with the definition
+    fn row_latitudes(&self) -> &[f64] {
+        self.row_latitudes.0.get_or_init(|| {
+            let all = shared_gaussian_latitudes(self.parallels_between_pole_and_equator.max(0) as usize);
+            let first = self.first_gridpoint().0;
+
+            let start = all
+                .iter()
+                .enumerate()
+                .min_by(|a, b| (a.1 - first).abs().total_cmp(&(b.1 - first).abs()))
+                .map_or(0, |closest| closest.0);
+
+            // the full set runs north to south, which is the default scanning direction for j
+            let (_, dj) = scan_directions(self.scanning_mode);
+
+            (0..self.row_count())
+                .filter_map(|row| {
+                    let index = start as i64 - dj as i64 * row as i64;
+                    if index < 0 { None } else { all.get(index as usize).copied() }
+                })
+                .collect()
+        })
+    }
+
+    // signed longitude step between neighbouring points of the given row
+    pub fn row_increment(&self, row: usize) -> f64 {
+        let (di, _) = scan_directions(self.scanning_mode);
+
+        if !self.is_reduced() && self.resolution_component_flags & 0x20 != 0 && self.direction_increment_i != MISSING_U32 {
+            return di * self.direction_increment_i as f64 * self.angle_unit();
+        }
+
+        let widest = self.list_point_counts.iter().copied().max().unwrap_or(self.parallel_point_count);
+        row_increment(self.scanning_mode, self.first_gridpoint().1, self.last_gridpoint().1, self.row_point_count(row), widest as usize)
+    }
+
+    /*
+    Latitude and longitude in degrees of point i of row j, both counted in scanning order.
+    Fractional row indices interpolate between the latitudes of neighbouring rows.
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let latitudes = self.row_latitudes();
+        let row = (j.max(0.0).floor() as usize).min(latitudes.len().saturating_sub(1));
+        let next = (row + 1).min(latitudes.len().saturating_sub(1));
+        let fraction = j - row as f64;
+
+        let lat = match (latitudes.get(row), latitudes.get(next)) {
+            (Some(a), Some(b)) => a + (b - a) * fraction,
+            _ => 0.0
+        };
+
+        (lat, self.first_gridpoint().1 + i * self.row_increment(j.round().max(0.0) as usize))
+    }
+
+    // longitudes of the columns in scanning order, empty for reduced grids
+    pub fn longitudes(&self) -> Vec<f64> {
+        if self.is_reduced() {
+            return Vec::new();
+        }
+
+        let (lon, increment) = (self.first_gridpoint().1, self.row_increment(0));
+        (0..self.row_point_count(0)).map(|i| lon + i as f64 * increment).collect()
+    }
+
+    // the latitude and longitude of every point, in the order the points are stored
+    pub fn coordinates(&self) -> Vec<(f64, f64)> {
+        let latitudes = self.row_latitudes();
+        let lon = self.first_gridpoint().1;
+
+        latitudes
+            .iter()
+            .enumerate()
+            .flat_map(|(row, lat)| {
+                let increment = self.row_increment(row);
+                (0..self.row_point_count(row)).map(move |i| (*lat, lon + i as f64 * increment))
+            })
+            .collect()
+    }
+
+    /*
+    The inverse of latlon(). Rows are found by searching the Gaussian latitudes, and the point
+    index is relative to the row nearest to the latitude.
+    */
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let latitudes = self.row_latitudes();
+
+        if latitudes.len() < 2 {
+            return (0.0, 0.0);
+        }
+
+        // the first row at or beyond the latitude, going in the direction the rows run
+        let direction = (latitudes[1] - latitudes[0]).signum();
+        let last = latitudes.len() - 1;
+        let j = match latitudes.partition_point(|row_lat| direction * (row_lat - lat) < 0.0) {
+            0 => (lat - latitudes[0]) / (latitudes[1] - latitudes[0]),
+            row if row > last => last as f64 + (lat - latitudes[last]) / (latitudes[last] - latitudes[last - 1]),
+            row => (row - 1) as f64 + (lat - latitudes[row - 1]) / (latitudes[row] - latitudes[row - 1])
+        };
+
+        let increment = self.row_increment(j.round().clamp(0.0, last as f64) as usize);
+        let i = if increment == 0.0 {
+            0.0
+        } else {
+            (increment.signum() * (lon - self.first_gridpoint().1)).rem_euclid(360.0) / increment.abs()
+        };
+
+        (i, j)
+    }
+}
+
+// grid definition template 3.1, a lat/lon grid on a sphere with a displaced pole
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct RotatedLatLonGridDefinition {
+    // the grid in rotated latitude and longitude
+    pub rotated_grid: LatLonGridDefinition,
+    pub southern_pole_latitude: i64,
+    pub southern_pole_longitude: i64,
+    pub rotation_angle: i64
+}
+
+impl RotatedLatLonGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<RotatedLatLonGridDefinition> {
+        // the first 19 values match template 3.0, any list of row point counts follows the rotation
+        let mut lat_lon_template: Vec<i64> = grid_template.get(0..19)?.to_vec();
+        lat_lon_template.extend(grid_template.iter().skip(22));
+
+        Some(RotatedLatLonGridDefinition {
+            rotated_grid: LatLonGridDefinition::new(&lat_lon_template)?,
+            southern_pole_latitude: *grid_template.get(19)?,
+            southern_pole_longitude: *grid_template.get(20)?,
+            rotation_angle: *grid_template.get(21)?
+        })
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        self.rotated_grid.earth_model()
+    }
+
+    // latitude and longitude in degrees of the southern pole of the rotated grid
+    pub fn southern_pole(&self) -> (f64, f64) {
+        (
+            self.southern_pole_latitude as f64 * self.rotated_grid.angle_unit(),
+            self.southern_pole_longitude as f64 * self.rotated_grid.angle_unit()
+        )
+    }
+
+    // the angle of rotation is stored as an IEEE 32 bit float
+    pub fn rotation_angle_degrees(&self) -> f64 {
+        f32::from_bits(self.rotation_angle as u32) as f64
+    }
+
+    fn rotation(&self) -> PoleRotation {
+        let (lat, lon) = self.southern_pole();
+        PoleRotation::new(lat, lon, self.rotation_angle_degrees())
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        self.rotated_grid.grid_shape()
+    }
+
+    // geographic latitude and longitude in degrees of grid point (i, j), longitudes are in [-180, 180)
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let (lat, lon) = self.rotated_grid.latlon(i, j);
+        self.rotation().to_geographic(lat, lon)
+    }
+
+    // the inverse of latlon(), giving fractional grid indices for a geographic latitude and longitude
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (rlat, rlon) = self.rotation().to_rotated(lat, lon);
+        self.rotated_grid.grid_index(rlat, rlon)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum ArakawaStaggering {
+    E,
+    B
+}
+
+/*
+NCEP local grid definition templates 3.32768 (Arakawa E) and 3.32769 (Arakawa B), rotated
+lat/lon grids used by the NMM based models. The first grid point is given in geographic
+coordinates, and the rotation moves the centre point to the rotated equator and prime meridian.
+On the E grid, every other row is shifted by half an increment along i.
+*/
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct ArakawaRotatedLatLonGridDefinition {
+    pub staggering: ArakawaStaggering,
+    pub earth_shape: u8,
+    pub earth_radius_scale_factor: u8,
+    pub earth_radius_scale_value: i64,
+    pub earth_oblate_spheroid_major_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_major_axis_scale_value: i64,
+    pub earth_oblate_spheroid_minor_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_minor_axis_scale_value: i64,
+    pub parallel_point_count: i64,
+    pub meridional_point_count: i64,
+    pub init_production_domain_basic_angle: i64,
+    pub basic_angle_subdivisions: i64,
+    pub first_gridpoint_latitude: i64,
+    pub first_gridpoint_longitude: i64,
+    pub resolution_component_flags: u8,
+    pub centre_latitude: i64,
+    pub centre_longitude: i64,
+    pub direction_increment_i: i64,
+    pub direction_increment_j: i64,
+    pub scanning_mode: u8,
+    // only present in template 3.32769
+    pub last_gridpoint_latitude: Option<i64>,
+    pub last_gridpoint_longitude: Option<i64>
+}
+
+impl ArakawaRotatedLatLonGridDefinition {
+    pub fn new(staggering: ArakawaStaggering, grid_template: &[i64]) -> Option<ArakawaRotatedLatLonGridDefinition> {
+        Some(ArakawaRotatedLatLonGridDefinition {
+            staggering,
+            earth_shape: *grid_template.first()? as u8,
+            earth_radius_scale_factor: *grid_template.get(1)? as u8,
+            earth_radius_scale_value: *grid_template.get(2)?,
+            earth_oblate_spheroid_major_axis_scale_factor: *grid_template.get(3)? as u8,
+            earth_oblate_spheroid_major_axis_scale_value: *grid_template.get(4)?,
+            earth_oblate_spheroid_minor_axis_scale_factor: *grid_template.get(5)? as u8,
+            earth_oblate_spheroid_minor_axis_scale_value: *grid_template.get(6)?,
+            parallel_point_count: *grid_template.get(7)?,
+            meridional_point_count: *grid_template.get(8)?,
+            init_production_domain_basic_angle: *grid_template.get(9)?,
+            basic_angle_subdivisions: *grid_template.get(10)?,
+            first_gridpoint_latitude: *grid_template.get(11)?,
+            first_gridpoint_longitude: *grid_template.get(12)?,
+            resolution_component_flags: *grid_template.get(13)? as u8,
+            centre_latitude: *grid_template.get(14)?,
+            centre_longitude: *grid_template.get(15)?,
+            direction_increment_i: *grid_template.get(16)?,
+            direction_increment_j: *grid_template.get(17)?,
+            scanning_mode: *grid_template.get(18)? as u8,
+            last_gridpoint_latitude: grid_template.get(19).copied(),
+            last_gridpoint_longitude: grid_template.get(20).copied()
+        })
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
+    // degrees per unit of the template's angles, 10^-6 unless a basic angle is given
+    pub fn angle_unit(&self) -> f64 {
+        angle_unit(self.init_production_domain_basic_angle, self.basic_angle_subdivisions)
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        (self.parallel_point_count as usize, self.meridional_point_count as usize)
+    }
+
+    fn rotation(&self) -> PoleRotation {
+        PoleRotation::new(
+            self.centre_latitude as f64 * self.angle_unit() - 90.0,
+            self.centre_longitude as f64 * self.angle_unit(),
+            0.0)
+    }
+
+    // rotated position of the first grid point, and the signed increments along i and j
+    fn rotated_layout(&self, rotation: &PoleRotation) -> ((f64, f64), (f64, f64)) {
+        let first = rotation.to_rotated(
+            self.first_gridpoint_latitude as f64 * self.angle_unit(),
+            self.first_gridpoint_longitude as f64 * self.angle_unit());
+        let (di, dj) = scan_directions(self.scanning_mode);
+
+        (
+            first,
+            (
+                di * self.direction_increment_i as f64 * self.angle_unit(),
+                dj * self.direction_increment_j as f64 * self.angle_unit()
+            )
+        )
+    }
+
+    // the shift along i of the given row, in increments
+    fn row_shift(&self, j: f64) -> f64 {
+        match self.staggering {
+            ArakawaStaggering::E if (j.round() as i64).rem_euclid(2) == 1 => 0.5,
+            _ => 0.0
+        }
+    }
+
+    // geographic latitude and longitude in degrees of grid point (i, j), longitudes are in [-180, 180)
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let rotation = self.rotation();
+        let ((lat, lon), (di, dj)) = self.rotated_layout(&rotation);
+
+        rotation.to_geographic(lat + j * dj, lon + (i + self.row_shift(j)) * di)
+    }
+
+    // the inverse of latlon(), giving fractional grid indices for a geographic latitude and longitude
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let rotation = self.rotation();
+        let ((lat0, lon0), (di, dj)) = self.rotated_layout(&rotation);
+        let (rlat, rlon) = rotation.to_rotated(lat, lon);
+
+        let j = (rlat - lat0) / dj;
+        (crate::projection::wrap_radians((rlon - lon0).to_radians()).to_degrees() / di - self.row_shift(j), j)
+    }
+}
+
+// grid definition template 3.10, used by some ocean wave and tropical cyclone models
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct MercatorGridDefinition {
+    pub earth_shape: u8,
+    pub earth_radius_scale_factor: u8,
+    pub earth_radius_scale_value: i64,
+    pub earth_oblate_spheroid_major_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_major_axis_scale_value: i64,
+    pub earth_oblate_spheroid_minor_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_minor_axis_scale_value: i64,
+    pub parallel_point_count: i64,
+    pub meridional_point_count: i64,
+    pub first_gridpoint_latitude: i64,
+    pub first_gridpoint_longitude: i64,
+    pub resolution_component_flags: u8,
+    pub increments_latitude: i64,
+    pub last_gridpoint_latitude: i64,
+    pub last_gridpoint_longitude: i64,
+    pub scanning_mode: u8,
+    pub grid_orientation: i64,
+    pub direction_increment_i: i64,
+    pub direction_increment_j: i64
+}
+
+impl MercatorGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<MercatorGridDefinition> {
+        Some(MercatorGridDefinition {
+            earth_shape: *grid_template.first()? as u8,
+            earth_radius_scale_factor: *grid_template.get(1)? as u8,
+            earth_radius_scale_value: *grid_template.get(2)?,
+            earth_oblate_spheroid_major_axis_scale_factor: *grid_template.get(3)? as u8,
+            earth_oblate_spheroid_major_axis_scale_value: *grid_template.get(4)?,
+            earth_oblate_spheroid_minor_axis_scale_factor: *grid_template.get(5)? as u8,
+            earth_oblate_spheroid_minor_axis_scale_value: *grid_template.get(6)?,
+            parallel_point_count: *grid_template.get(7)?,
+            meridional_point_count: *grid_template.get(8)?,
+            first_gridpoint_latitude: *grid_template.get(9)?,
+            first_gridpoint_longitude: *grid_template.get(10)?,
+            resolution_component_flags: *grid_template.get(11)? as u8,
+            increments_latitude: *grid_template.get(12)?,
+            last_gridpoint_latitude: *grid_template.get(13)?,
+            last_gridpoint_longitude: *grid_template.get(14)?,
+            scanning_mode: *grid_template.get(15)? as u8,
+            grid_orientation: *grid_template.get(16)?,
+            direction_increment_i: *grid_template.get(17)?,
+            direction_increment_j: *grid_template.get(18)?
+        })
+    }
+
+    pub fn earth_shape(&self, code_table_3_2: &Codetable) -> Option<String> {
+        code_table_3_2.codepoint_lookup(
+            self.earth_shape as i64,
+            code_table_3_2.find_parameter("Meaning")?
+        )
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
+    pub fn lat_span(&self) -> (i64, i64) {
+        (self.first_gridpoint_latitude, self.last_gridpoint_latitude)
+    }
+
+    pub fn lon_span(&self) -> (i64, i64) {
+        (self.first_gridpoint_longitude, self.last_gridpoint_longitude)
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        (self.parallel_point_count as usize, self.meridional_point_count as usize)
+    }
+
+    // centring the projection on the grid keeps grids spanning the globe clear of the wrap-around
+    pub(crate) fn central_longitude(&self) -> f64 {
+        let (di, _) = scan_directions(self.scanning_mode);
+        let span = (di * (self.last_gridpoint_longitude - self.first_gridpoint_longitude) as f64 * MICRO_DEGREES).rem_euclid(360.0);
+
+        self.first_gridpoint_longitude as f64 * MICRO_DEGREES + di * span / 2.0
+    }
+
+    pub(crate) fn projection(&self) -> Mercator {
+        let (a, e) = self.earth_model().ellipsoid();
+
+        Mercator::new(
+            a,
+            e,
+            self.increments_latitude as f64 * MICRO_DEGREES,
+            self.central_longitude())
+    }
+
+    // position of the first grid point on the plane, and the grid axes scaled by Di and Dj
+    fn plane_layout(&self, projection: &Mercator) -> ((f64, f64), (f64, f64), (f64, f64)) {
+        let origin = projection.forward(
+            self.first_gridpoint_latitude as f64 * MICRO_DEGREES,
+            self.first_gridpoint_longitude as f64 * MICRO_DEGREES);
+
+        // the i axis of the grid is turned away from the equator by the orientation angle
+        let (di, dj) = scan_directions(self.scanning_mode);
+        let (sin, cos) = (self.grid_orientation as f64 * MICRO_DEGREES).to_radians().sin_cos();
+        let (dx, dy) = (
+            di * self.direction_increment_i as f64 * MILLIMETRES,
+            dj * self.direction_increment_j as f64 * MILLIMETRES
+        );
+
+        (origin, (cos * dx, sin * dx), (-sin * dy, cos * dy))
+    }
+
+    /*
+    Latitude and longitude in degrees of grid point (i, j), counted from the first grid point
+    in the scanning directions. Fractional indices are allowed, longitudes are in [-180, 180).
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let projection = self.projection();
+        let ((x0, y0), (ix, iy), (jx, jy)) = self.plane_layout(&projection);
+
+        projection.inverse(x0 + i * ix + j * jx, y0 + i * iy + j * jy)
+    }
+
+    // the inverse of latlon(), giving fractional grid indices for a latitude and longitude
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let projection = self.projection();
+        let ((x0, y0), (ix, iy), (jx, jy)) = self.plane_layout(&projection);
+        let (x, y) = projection.forward(lat, lon);
+        let (rx, ry) = (x - x0, y - y0);
+
+        // solve the 2x2 system spanned by the grid axes
+        let det = ix * jy - jx * iy;
+        ((rx * jy - jx * ry) / det, (ix * ry - rx * iy) / det)
+    }
+}
+
+// grid definition template 3.20, used for sea ice, snow cover and other polar products
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct PolarStereographicGridDefinition {
+    pub earth_shape: u8,
+    pub earth_radius_scale_factor: u8,
+    pub earth_radius_scale_value: i64,
+    pub earth_oblate_spheroid_major_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_major_axis_scale_value: i64,
+    pub earth_oblate_spheroid_minor_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_minor_axis_scale_value: i64,
+    pub x_point_count: i64,
+    pub y_point_count: i64,
+    pub first_gridpoint_latitude: i64,
+    pub first_gridpoint_longitude: i64,
+    pub resolution_component_flags: u8,
+    pub increments_latitude: i64,
+    pub orientation_longitude: i64,
+    pub direction_increment_x: i64,
+    pub direction_increment_y: i64,
+    pub projection_centre_flags: u8,
+    pub scanning_mode: u8
+}
+
+impl PolarStereographicGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<PolarStereographicGridDefinition> {
+        Some(PolarStereographicGridDefinition {
+            earth_shape: *grid_template.first()? as u8,
+            earth_radius_scale_factor: *grid_template.get(1)? as u8,
+            earth_radius_scale_value: *grid_template.get(2)?,
+            earth_oblate_spheroid_major_axis_scale_factor: *grid_template.get(3)? as u8,
+            earth_oblate_spheroid_major_axis_scale_value: *grid_template.get(4)?,
+            earth_oblate_spheroid_minor_axis_scale_factor: *grid_template.get(5)? as u8,
+            earth_oblate_spheroid_minor_axis_scale_value: *grid_template.get(6)?,
+            x_point_count: *grid_template.get(7)?,
+            y_point_count: *grid_template.get(8)?,
+            first_gridpoint_latitude: *grid_template.get(9)?,
+            first_gridpoint_longitude: *grid_template.get(10)?,
+            resolution_component_flags: *grid_template.get(11)? as u8,
+            increments_latitude: *grid_template.get(12)?,
+            orientation_longitude: *grid_template.get(13)?,
+            direction_increment_x: *grid_template.get(14)?,
+            direction_increment_y: *grid_template.get(15)?,
+            projection_centre_flags: *grid_template.get(16)? as u8,
+            scanning_mode: *grid_template.get(17)? as u8
+        })
+    }
+
+    pub fn earth_shape(&self, code_table_3_2: &Codetable) -> Option<String> {
+        code_table_3_2.codepoint_lookup(
+            self.earth_shape as i64,
+            code_table_3_2.find_parameter("Meaning")?
+        )
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
+    // the south pole is on the projection plane when bit 1 of the projection centre flag is set
+    pub fn is_south_pole_centred(&self) -> bool {
+        self.projection_centre_flags & 0x80 != 0
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        (self.x_point_count as usize, self.y_point_count as usize)
+    }
+
+    // oblate spheroid earth shapes get the ellipsoidal form of the projection
+    pub(crate) fn projection(&self) -> PolarStereographic {
+        let (a, e) = self.earth_model().ellipsoid();
+
+        PolarStereographic::new(
+            a,
+            e,
+            self.is_south_pole_centred(),
+            self.increments_latitude as f64 * MICRO_DEGREES,
+            self.orientation_longitude as f64 * MICRO_DEGREES)
+    }
+
+    // position of the first grid point on the plane, and the grid lengths on the plane
+    fn plane_layout(&self, projection: &PolarStereographic) -> ((f64, f64), (f64, f64)) {
+        let origin = projection.forward(
+            self.first_gridpoint_latitude as f64 * MICRO_DEGREES,
+            self.first_gridpoint_longitude as f64 * MICRO_DEGREES);
+
+        // the projection is true to scale at LaD, where Dx and Dy are given
+        let (di, dj) = scan_directions(self.scanning_mode);
+
+        (
+            origin,
+            (
+                di * self.direction_increment_x as f64 * MILLIMETRES,
+                dj * self.direction_increment_y as f64 * MILLIMETRES
+            )
+        )
+    }
+
+    /*
+    Latitude and longitude in degrees of grid point (i, j), counted from the first grid point
+    in the scanning directions. Fractional indices are allowed, longitudes are in [-180, 180).
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let projection = self.projection();
+        let ((x0, y0), (dx, dy)) = self.plane_layout(&projection);
+
+        projection.inverse(x0 + i * dx, y0 + j * dy)
+    }
+
+    // the inverse of latlon(), giving fractional grid indices for a latitude and longitude
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let projection = self.projection();
+        let ((x0, y0), (dx, dy)) = self.plane_layout(&projection);
+        let (x, y) = projection.forward(lat, lon);
+
+        ((x - x0) / dx, (y - y0) / dy)
+    }
+}
+
+// grid definition template 3.30, used by HRRR, NAM and RAP among others
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct LambertConformalGridDefinition {
+    pub earth_shape: u8,
+    pub earth_radius_scale_factor: u8,
+    pub earth_radius_scale_value: i64,
+    pub earth_oblate_spheroid_major_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_major_axis_scale_value: i64,
+    pub earth_oblate_spheroid_minor_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_minor_axis_scale_value: i64,
+    pub x_point_count: i64,
+    pub y_point_count: i64,
+    pub first_gridpoint_latitude: i64,
+    pub first_gridpoint_longitude: i64,
+    pub resolution_component_flags: u8,
+    pub increments_latitude: i64,
+    pub orientation_longitude: i64,
+    pub direction_increment_x: i64,
+    pub direction_increment_y: i64,
+    pub projection_centre_flags: u8,
+    pub scanning_mode: u8,
+    pub first_secant_latitude: i64,
+    pub second_secant_latitude: i64,
+    pub southern_pole_latitude: i64,
+    pub southern_pole_longitude: i64
+}
+
+impl LambertConformalGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<LambertConformalGridDefinition> {
+        Some(LambertConformalGridDefinition {
+            earth_shape: *grid_template.first()? as u8,
+            earth_radius_scale_factor: *grid_template.get(1)? as u8,
+            earth_radius_scale_value: *grid_template.get(2)?,
+            earth_oblate_spheroid_major_axis_scale_factor: *grid_template.get(3)? as u8,
+            earth_oblate_spheroid_major_axis_scale_value: *grid_template.get(4)?,
+            earth_oblate_spheroid_minor_axis_scale_factor: *grid_template.get(5)? as u8,
+            earth_oblate_spheroid_minor_axis_scale_value: *grid_template.get(6)?,
+            x_point_count: *grid_template.get(7)?,
+            y_point_count: *grid_template.get(8)?,
+            first_gridpoint_latitude: *grid_template.get(9)?,
+            first_gridpoint_longitude: *grid_template.get(10)?,
+            resolution_component_flags: *grid_template.get(11)? as u8,
+            increments_latitude: *grid_template.get(12)?,
+            orientation_longitude: *grid_template.get(13)?,
+            direction_increment_x: *grid_template.get(14)?,
+            direction_increment_y: *grid_template.get(15)?,
+            projection_centre_flags: *grid_template.get(16)? as u8,
+            scanning_mode: *grid_template.get(17)? as u8,
+            first_secant_latitude: *grid_template.get(18)?,
+            second_secant_latitude: *grid_template.get(19)?,
+            southern_pole_latitude: *grid_template.get(20)?,
+            southern_pole_longitude: *grid_template.get(21)?
+        })
+    }
+
+    pub fn earth_shape(&self, code_table_3_2: &Codetable) -> Option<String> {
+        code_table_3_2.codepoint_lookup(
+            self.earth_shape as i64,
+            code_table_3_2.find_parameter("Meaning")?
+        )
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
+    // the south pole is on the projection plane when bit 1 of the projection centre flag is set
+    pub fn is_south_pole_centred(&self) -> bool {
+        self.projection_centre_flags & 0x80 != 0
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        (self.x_point_count as usize, self.y_point_count as usize)
+    }
+
+    pub(crate) fn projection(&self) -> LambertConic {
+        let (a, e) = self.earth_model().ellipsoid();
+
+        LambertConic::new(
+            a,
+            e,
+            self.first_secant_latitude as f64 * MICRO_DEGREES,
+            self.second_secant_latitude as f64 * MICRO_DEGREES,
+            self.orientation_longitude as f64 * MICRO_DEGREES)
+    }
+
+    // position of the first grid point on the plane, and the grid lengths on the plane
+    fn plane_layout(&self, projection: &LambertConic) -> ((f64, f64), (f64, f64)) {
+        let origin = projection.forward(
+            self.first_gridpoint_latitude as f64 * MICRO_DEGREES,
+            self.first_gridpoint_longitude as f64 * MICRO_DEGREES);
+
+        // Dx and Dy are true at LaD, which need not be one of the secant latitudes
+        let scale = projection.scale(self.increments_latitude as f64 * MICRO_DEGREES);
+        let (di, dj) = scan_directions(self.scanning_mode);
+
+        (
+            origin,
+            (
+                di * scale * self.direction_increment_x as f64 * MILLIMETRES,
+                dj * scale * self.direction_increment_y as f64 * MILLIMETRES
+            )
+        )
+    }
+
+    /*
+    Latitude and longitude in degrees of grid point (i, j), counted from the first grid point
+    in the scanning directions. Fractional indices are allowed, longitudes are in [-180, 180).
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> (f64, f64) {
+        let projection = self.projection();
+        let ((x0, y0), (dx, dy)) = self.plane_layout(&projection);
+
+        projection.inverse(x0 + i * dx, y0 + j * dy)
+    }
+
+    // the inverse of latlon(), giving fractional grid indices for a latitude and longitude
+    pub fn grid_index(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let projection = self.projection();
+        let ((x0, y0), (dx, dy)) = self.plane_layout(&projection);
+        let (x, y) = projection.forward(lat, lon);
+
+        ((x - x0) / dx, (y - y0) / dy)
+    }
+}
+
+// grid definition template 3.90, images as seen from a geostationary satellite
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct SpaceViewGridDefinition {
+    pub earth_shape: u8,
+    pub earth_radius_scale_factor: u8,
+    pub earth_radius_scale_value: i64,
+    pub earth_oblate_spheroid_major_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_major_axis_scale_value: i64,
+    pub earth_oblate_spheroid_minor_axis_scale_factor: u8,
+    pub earth_oblate_spheroid_minor_axis_scale_value: i64,
+    pub x_point_count: i64,
+    pub y_point_count: i64,
+    pub sub_satellite_point_latitude: i64,
+    pub sub_satellite_point_longitude: i64,
+    pub resolution_component_flags: u8,
+    pub apparent_diameter_x: i64,
+    pub apparent_diameter_y: i64,
+    pub sub_satellite_point_x: i64,
+    pub sub_satellite_point_y: i64,
+    pub scanning_mode: u8,
+    pub grid_orientation: i64,
+    pub camera_altitude: i64,
+    pub sector_origin_x: i64,
+    pub sector_origin_y: i64
+}
+
+impl SpaceViewGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<SpaceViewGridDefinition> {
+        Some(SpaceViewGridDefinition {
+            earth_shape: *grid_template.first()? as u8,
+            earth_radius_scale_factor: *grid_template.get(1)? as u8,
+            earth_radius_scale_value: *grid_template.get(2)?,
+            earth_oblate_spheroid_major_axis_scale_factor: *grid_template.get(3)? as u8,
+            earth_oblate_spheroid_major_axis_scale_value: *grid_template.get(4)?,
+            earth_oblate_spheroid_minor_axis_scale_factor: *grid_template.get(5)? as u8,
+            earth_oblate_spheroid_minor_axis_scale_value: *grid_template.get(6)?,
+            x_point_count: *grid_template.get(7)?,
+            y_point_count: *grid_template.get(8)?,
+            sub_satellite_point_latitude: *grid_template.get(9)?,
+            sub_satellite_point_longitude: *grid_template.get(10)?,
+            resolution_component_flags: *grid_template.get(11)? as u8,
+            apparent_diameter_x: *grid_template.get(12)?,
+            apparent_diameter_y: *grid_template.get(13)?,
+            sub_satellite_point_x: *grid_template.get(14)?,
+            sub_satellite_point_y: *grid_template.get(15)?,
+            scanning_mode: *grid_template.get(16)? as u8,
+            grid_orientation: *grid_template.get(17)?,
+            camera_altitude: *grid_template.get(18)?,
+            sector_origin_x: *grid_template.get(19)?,
+            sector_origin_y: *grid_template.get(20)?
+        })
+    }
+
+    pub fn earth_shape(&self, code_table_3_2: &Codetable) -> Option<String> {
+        code_table_3_2.codepoint_lookup(
+            self.earth_shape as i64,
+            code_table_3_2.find_parameter("Meaning")?
+        )
+    }
+
+    pub fn earth_model(&self) -> EarthModel {
+        earth_model!(self)
+    }
+
+    pub fn grid_shape(&self) -> (usize, usize) {
+        (self.x_point_count as usize, self.y_point_count as usize)
+    }
+
+    pub fn sub_satellite_point(&self) -> (f64, f64) {
+        (
+            self.sub_satellite_point_latitude as f64 * MICRO_DEGREES,
+            self.sub_satellite_point_longitude as f64 * MICRO_DEGREES
+        )
+    }
+
+    // Nr is the distance of the camera from the centre of the earth, in units of 10^-6 equatorial radii
+    pub fn satellite_distance(&self) -> f64 {
+        self.earth_model().semi_major_axis() * self.camera_altitude as f64 * MICRO_RADII
+    }
+
+    // height of the camera above the equator in metres
+    pub fn altitude(&self) -> f64 {
+        self.satellite_distance() - self.earth_model().semi_major_axis()
+    }
+
+    fn projection(&self) -> Geostationary {
+        let (a, e) = self.earth_model().ellipsoid();
+        Geostationary::new(a, e, self.satellite_distance(), self.sub_satellite_point().1)
+    }
+
+    /*
+    Scan angles in radians covered by one grid length along x and y.
+    The apparent diameter is the number of grid lengths spanned by the disk of the earth.
+    */
+    fn angular_increments(&self) -> (f64, f64) {
+        let disk = 2.0 * (1.0 / (self.camera_altitude as f64 * MICRO_RADII)).asin();
+        (disk / self.apparent_diameter_x as f64, disk / self.apparent_diameter_y as f64)
+    }
+
+    // scan angles of grid point (i, j), turned by the orientation of the grid
+    pub(crate) fn scan_angles(&self, i: f64, j: f64) -> (f64, f64) {
+        let (di, dj) = scan_directions(self.scanning_mode);
+        let (rx, ry) = self.angular_increments();
+
+        // Xp and Yp are given in units of 10^-3 grid lengths from the origin of the full image
+        let x = di * (i + self.sector_origin_x as f64 - self.sub_satellite_point_x as f64 * 1e-3) * rx;
+        let y = dj * (j + self.sector_origin_y as f64 - self.sub_satellite_point_y as f64 * 1e-3) * ry;
+
+        let (sin, cos) = (self.grid_orientation as f64 * MICRO_DEGREES).to_radians().sin_cos();
+        (cos * x - sin * y, sin * x + cos * y)
+    }
+
+    /*
+    Latitude and longitude in degrees of grid point (i, j), counted from the first grid point
+    in the scanning directions. Points which do not look at the earth are None.
+    */
+    pub fn latlon(&self, i: f64, j: f64) -> Option<(f64, f64)> {
+        let (x, y) = self.scan_angles(i, j);
+        self.projection().inverse(x, y)
+    }
+
+    // whether grid point (i, j) falls on the disk of the earth
+    pub fn is_on_disk(&self, i: f64, j: f64) -> bool {
+        self.latlon(i, j).is_some()
+    }
+
+    // the inverse of latlon(), None for points hidden behind the earth
+    pub fn grid_index(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (x, y) = self.projection().forward(lat, lon)?;
+
+        let (sin, cos) = (self.grid_orientation as f64 * MICRO_DEGREES).to_radians().sin_cos();
+        let (x, y) = (cos * x + sin * y, -sin * x + cos * y);
+
+        let (di, dj) = scan_directions(self.scanning_mode);
+        let (rx, ry) = self.angular_increments();
+
+        Some((
+            x / (di * rx) + self.sub_satellite_point_x as f64 * 1e-3 - self.sector_origin_x as f64,
+            y / (dj * ry) + self.sub_satellite_point_y as f64 * 1e-3 - self.sector_origin_y as f64
+        ))
+    }
+}
+
+/*
+Grid definition template 3.101, a general unstructured grid such as ICON's triangular mesh.
+The message only names the grid, the coordinates of its points have to come from a grid
+description file distributed separately.
+*/
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct UnstructuredGridDefinition {
+    pub earth_shape: u8,
+    pub grid_number: u32,
+    pub grid_reference: u8,
+    pub uuid: [u8; 16]
+}
+
+impl UnstructuredGridDefinition {
+    pub fn new(grid_template: &[i64]) -> Option<UnstructuredGridDefinition> {
+        let mut uuid = [0u8; 16];
+        let uuid_values = grid_template.get(3..)?;
+
+        // the UUID comes out of g2c either as 16 single octets or as four 4 octet integers
+        match uuid_values.len() {
+            16 => {
+                for (octet, value) in uuid.iter_mut().zip(uuid_values) {
+                    *octet = *value as u8;
+                }
+            },
+            4 => {
+                for (chunk, value) in uuid.chunks_mut(4).zip(uuid_values) {
+                    chunk.copy_from_slice(&(*value as u32).to_be_bytes());
+                }
+            },
+            _ => return None
+        }
+
+        Some(UnstructuredGridDefinition {
+            earth_shape: *grid_template.first()? as u8,
+            grid_number: *grid_template.get(1)? as u32,
+            grid_reference: *grid_template.get(2)? as u8,
+            uuid
+        })
+    }
+
+    pub fn earth_shape(&self, code_table_3_2: &Codetable) -> Option<String> {
+        code_table_3_2.codepoint_lookup(
+            self.earth_shape as i64,
+            code_table_3_2.find_parameter("Meaning")?
+        )
+    }
+
+    // the template carries no radius or axes, so only the fixed shapes are meaningful
+    pub fn earth_model(&self) -> EarthModel {
+        EarthModel::from_template(self.earth_shape, 0, 0, 0, 0, 0, 0)
+    }
+
+    // the UUID in its usual 8-4-4-4-12 hexadecimal form
+    pub fn uuid_string(&self) -> String {
+        let hex: String = self.uuid.iter().map(|octet| format!("{:02x}", octet)).collect();
+        format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+    }
+}
+
+/*
+Coordinates of the points of an unstructured grid, in degrees and in the order the
+points are stored in the message, with the UUID of the grid they describe.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnstructuredGridCoordinates {
+    pub uuid: [u8; 16],
+    latitudes: Vec<f64>,
+    longitudes: Vec<f64>
+}
+
+impl UnstructuredGridCoordinates {
+    pub fn new(uuid: [u8; 16], latitudes: Vec<f64>, longitudes: Vec<f64>) -> Option<UnstructuredGridCoordinates> {
+        if latitudes.len() != longitudes.len() {
+            return None;
+        }
+
+        Some(UnstructuredGridCoordinates {
+            uuid,
+            latitudes,
+            longitudes
+        })
+    }
+
+    /*
+    Reads a grid description from a CSV file with one row per grid point.
+    The first line names the grid the file describes, as "uuid,<UUID>" with the UUID written
+    as 32 hex digits, with or without dashes. Latitudes and longitudes follow, taken from the
+    columns headed "lat"/"latitude" and "lon"/"longitude", in degrees.
+    */
+    pub fn from_csv(path: &Path) -> Result<UnstructuredGridCoordinates, Grib2Error> {
+        let file = File::open(path).map_err(|why| Grib2Error::InvalidGridFile(why.to_string()))?;
+        let mut reader = BufReader::new(file);
+
+        let mut uuid_line = String::new();
+        reader.read_line(&mut uuid_line).map_err(|why| Grib2Error::InvalidGridFile(why.to_string()))?;
+        let uuid = parse_uuid_line(&uuid_line).ok_or(Grib2Error::InvalidGridFile(String::from("no grid uuid on the first line")))?;
+
+        let mut csvreader = csv::Reader::from_reader(reader);
+
+        let headers: Vec<String> = csvreader
+            .headers()
+            .map_err(|why| Grib2Error::InvalidGridFile(why.to_string()))?
+            .iter()
+            .map(|x| x.trim().to_lowercase())
+            .collect();
+
+        let column = |names: &[&str]| headers.iter().position(|header| names.contains(&header.as_str()));
+        let lat_column = column(&["lat", "latitude"]).ok_or(Grib2Error::InvalidGridFile(String::from("no latitude column")))?;
+        let lon_column = column(&["lon", "longitude"]).ok_or(Grib2Error::InvalidGridFile(String::from("no longitude column")))?;
+
+        let mut latitudes = Vec::new();
+        let mut longitudes = Vec::new();
+
+        for record in csvreader.records() {
+            let record = record.map_err(|why| Grib2Error::InvalidGridFile(why.to_string()))?;
+            let value = |col: usize| -> Result<f64, Grib2Error> {
+                record
+                    .get(col)
+                    .and_then(|x| x.trim().parse::<f64>().ok())
+                    .ok_or(Grib2Error::InvalidGridFile(format!("bad coordinate on line {}", latitudes.len() + 3)))
+            };
+
+            let (lat, lon) = (value(lat_column)?, value(lon_column)?);
+            latitudes.push(lat);
+            longitudes.push(lon);
+        }
+
+        Ok(UnstructuredGridCoordinates {
+            uuid,
+            latitudes,
+            longitudes
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.latitudes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latitudes.is_empty()
+    }
+
+    pub fn latitudes(&self) -> &Vec<f64> {
+        &self.latitudes
+    }
+
+    pub fn longitudes(&self) -> &Vec<f64> {
+        &self.longitudes
+    }
+
+    pub fn latlon(&self, point: usize) -> Option<(f64, f64)> {
+        Some((*self.latitudes.get(point)?, *self.longitudes.get(point)?))
+    }
+
+    // whether these coordinates describe the given grid, by UUID and point count
+    pub fn describes(&self, grid: &UnstructuredGridDefinition, point_count: usize) -> bool {
+        self.uuid == grid.uuid && self.len() == point_count
+    }
+}
+
+// parses a "uuid,<UUID>" line into the 16 octets of the UUID
+fn parse_uuid_line(line: &str) -> Option<[u8; 16]> {
+    let mut parts = line.trim().splitn(2, ',');
+    if !parts.next()?.trim().eq_ignore_ascii_case("uuid") {
+        return None;
+    }
+
+    let hex: String = parts.next()?.trim().chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut uuid = [0u8; 16];
+    for (i, octet) in uuid.iter_mut().enumerate() {
+        *octet = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+
+    Some(uuid)
 }
@@ -0,0 +1,212 @@
+use crate::{Grib2Field, earth::EarthModel, grid::{GridDefinition, ScanningMode}, lookup::{point_storage_index, surrounding_points}};
+
+/*
+Interpolation of a field to arbitrary points.
+
+Bilinear and bicubic interpolation work in grid index space, so they apply to any grid with
+a fixed number of points per row, projected grids included. Inverse distance weighting uses
+the points surrounding the target and also works on reduced grids.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointInterpolation {
+    Bilinear,
+    // Keys cubic convolution over the surrounding 4 by 4 points
+    Bicubic,
+    // weights of 1 / distance^power over the surrounding points
+    InverseDistance {
+        power: f64
+    }
+}
+
+// everything needed to sample a field, worked out once for a batch of points
+struct FieldSampler {
+    grid: GridDefinition,
+    values: Vec<Option<f32>>,
+    counts: Vec<usize>,
+    shape: Option<(usize, usize, ScanningMode)>,
+    wraps: bool,
+    earth: EarthModel
+}
+
+// cubic convolution weights for the four points around a fraction t, Keys (1981) with a = -0.5
+fn cubic_weights(t: f64) -> [f64; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    [
+        -0.5 * t3 + t2 - 0.5 * t,
+        1.5 * t3 - 2.5 * t2 + 1.0,
+        -1.5 * t3 + 2.0 * t2 + 0.5 * t,
+        0.5 * t3 - 0.5 * t2
+    ]
+}
+
+// position in data() of grid point (i, j), wrapping i around the globe where the grid does
+fn storage_index(mode: ScanningMode, ni: usize, nj: usize, wraps: bool, i: i64, j: i64) -> Option<usize> {
+    let i = if wraps { i.rem_euclid(ni as i64) } else { i };
+
+    if i < 0 || j < 0 || i as usize >= ni || j as usize >= nj {
+        None
+    } else {
+        Some(mode.storage_index(i as usize, j as usize, ni, nj))
+    }
+}
+
+/*
+Fractional grid index of a position on a grid with a fixed number of points per row, None
+outside of the grid. Rounding at the edges is tolerated, and global grids reach the poles.
+*/
+pub(crate) fn grid_position(grid: &GridDefinition, lat: f64, lon: f64) -> Option<(f64, f64)> {
+    let (ni, nj) = grid.grid_shape()?;
+    let (i, j) = grid.grid_index(lat, lon)?;
+    let wraps = grid.wraps_longitude();
+
+    let (last_i, last_j) = ((ni - 1) as f64, (nj - 1) as f64);
+    let inside = |index: f64, last: f64| index > -1e-9 && index < last + 1e-9;
+
+    if !(wraps || inside(i, last_i) && inside(j, last_j)) {
+        return None;
+    }
+
+    Some((i.max(0.0).min(if wraps { ni as f64 } else { last_i }), j.max(0.0).min(last_j)))
+}
+
+/*
+Positions in data() and bilinear weights of the (up to four) grid points around a position.
+Points with no weight are left out.
+*/
+pub(crate) fn bilinear_weights(grid: &GridDefinition, lat: f64, lon: f64) -> Option<Vec<(usize, f64)>> {
+    let (ni, nj) = grid.grid_shape()?;
+    let mode = grid.scanning_mode()?;
+    let wraps = grid.wraps_longitude();
+
+    let (i, j) = grid_position(grid, lat, lon)?;
+    let (i0, j0) = (i.floor(), j.floor());
+    let (fi, fj) = (i - i0, j - j0);
+
+    let corners = [
+        (0, 0, (1.0 - fi) * (1.0 - fj)),
+        (1, 0, fi * (1.0 - fj)),
+        (0, 1, (1.0 - fi) * fj),
+        (1, 1, fi * fj)
+    ];
+
+    Some(
+        corners
+            .iter()
+            .filter(|corner| corner.2 > 0.0)
+            .filter_map(|(di, dj, weight)| Some((storage_index(mode, ni, nj, wraps, i0 as i64 + di, j0 as i64 + dj)?, *weight)))
+            .collect()
+    )
+}
+
+impl FieldSampler {
+    fn new(field: &Grib2Field) -> Option<FieldSampler> {
+        let grid = field.grid();
+        let counts = grid.row_point_counts()?;
+
+        Some(FieldSampler {
+            shape: match (grid.grid_shape(), grid.scanning_mode()) {
+                (Some((ni, nj)), Some(mode)) => Some((ni, nj, mode)),
+                _ => None
+            },
+            wraps: grid.wraps_longitude(),
+            earth: grid.earth_model().unwrap_or(EarthModel::Sphere(6371229.0)),
+            values: field.data_masked(),
+            counts,
+            grid
+        })
+    }
+
+    // the value at grid point (i, j), wrapping i around the globe where the grid does
+    fn value(&self, i: i64, j: i64) -> Option<f32> {
+        let (ni, nj, mode) = self.shape?;
+        *self.values.get(storage_index(mode, ni, nj, self.wraps, i, j)?)?
+    }
+
+    // missing corners are left out and the remaining weights renormalised
+    fn bilinear(&self, lat: f64, lon: f64) -> Option<f32> {
+        let (sum, weights) = bilinear_weights(&self.grid, lat, lon)?
+            .into_iter()
+            .filter_map(|(point, weight)| Some((self.values.get(point).copied()?? as f64 * weight, weight)))
+            .fold((0.0, 0.0), |acc, (value, weight)| (acc.0 + value, acc.1 + weight));
+
+        if weights > 0.0 { Some((sum / weights) as f32) } else { None }
+    }
+
+    // falls back to bilinear near the edges of the grid and next to missing points
+    fn bicubic(&self, lat: f64, lon: f64) -> Option<f32> {
+        let (i, j) = grid_position(&self.grid, lat, lon)?;
+        let (i0, j0) = (i.floor() as i64, j.floor() as i64);
+        let (wi, wj) = (cubic_weights(i - i0 as f64), cubic_weights(j - j0 as f64));
+
+        let mut sum = 0.0;
+
+        for (row, weight_j) in wj.iter().enumerate() {
+            for (column, weight_i) in wi.iter().enumerate() {
+                match self.value(i0 + column as i64 - 1, j0 + row as i64 - 1) {
+                    Some(value) => sum += value as f64 * weight_i * weight_j,
+                    None => return self.bilinear(lat, lon)
+                }
+            }
+        }
+
+        Some(sum as f32)
+    }
+
+    fn inverse_distance(&self, lat: f64, lon: f64, power: f64) -> Option<f32> {
+        let mut sum = 0.0;
+        let mut weights = 0.0;
+
+        for (row, point) in surrounding_points(&self.grid, lat, lon) {
+            let value = match self.values.get(point_storage_index(&self.grid, &self.counts, row, point)) {
+                Some(Some(value)) => *value as f64,
+                _ => continue
+            };
+
+            let (point_lat, point_lon) = match self.grid.latlon(point as f64, row as f64) {
+                Some(position) => position,
+                None => continue
+            };
+
+            let distance = self.earth.distance(lat, lon, point_lat, point_lon);
+            if distance < 1e-3 {
+                return Some(value as f32);
+            }
+
+            let weight = distance.powf(-power);
+            sum += value * weight;
+            weights += weight;
+        }
+
+        if weights > 0.0 { Some((sum / weights) as f32) } else { None }
+    }
+
+    fn sample(&self, lat: f64, lon: f64, method: PointInterpolation) -> Option<f32> {
+        match method {
+            PointInterpolation::Bilinear => self.bilinear(lat, lon),
+            PointInterpolation::Bicubic => self.bicubic(lat, lon),
+            PointInterpolation::InverseDistance { power } => self.inverse_distance(lat, lon, power)
+        }
+    }
+}
+
+impl Grib2Field {
+    /*
+    Interpolates the field to the given latitude and longitude in degrees.
+    None outside of the grid, where every neighbouring point is missing, or for grids which
+    cannot be located.
+    */
+    pub fn interpolate(&self, lat: f64, lon: f64, method: PointInterpolation) -> Option<f32> {
+        self.interpolate_points(&[(lat, lon)], method).pop().flatten()
+    }
+
+    // interpolates the field to many (latitude, longitude) points at once, one result per point
+    pub fn interpolate_points(&self, points: &[(f64, f64)], method: PointInterpolation) -> Vec<Option<f32>> {
+        match FieldSampler::new(self) {
+            Some(sampler) => points.iter().map(|(lat, lon)| sampler.sample(*lat, *lon, method)).collect(),
+            None => vec![None; points.len()]
+        }
+    }
+}
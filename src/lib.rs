@@ -1,21 +1,49 @@
 
-use std::{ffi::{CString}, alloc::{Layout, alloc, dealloc}, collections::HashMap, fmt::{Display, Formatter}, mem::size_of, u32};
+use std::{ffi::{CString}, alloc::{Layout, alloc, dealloc}, collections::HashMap, fmt::{Display, Formatter}, mem::size_of, sync::Arc, u32};
 
 use chrono::{DateTime, Utc};
 use chrono::prelude::*;
 use libg2c_sys;
 
+pub mod area;
+pub mod bitmap;
 pub mod codetables;
+pub mod crs;
+pub mod earth;
+pub mod edit;
 pub mod grid;
+pub mod interpolate;
+pub mod longitude;
+pub mod lookup;
+pub mod points;
 pub mod product;
-
-use grid::LatLonGridDefinition;
+mod projection;
+pub mod reduced;
+pub mod regrid;
+pub mod repack;
+pub mod sections;
+pub mod subset;
+pub mod wind;
+
+use bitmap::{Bitmap, BitmapRegistry};
+use grid::{GridCoordinates, GridDefinition, LatLonGridDefinition, UnstructuredGridCoordinates};
+use longitude::LongitudeConvention;
 use product::{HorizontalLayerProductDefinition, FixedSurface};
 
 #[derive(Debug, Clone)]
 pub enum Grib2Error {
     InfoReadError(u8),
     FieldReadError(u8),
+    MessageEncodeError(i8),
+    GridEncodeError(i8),
+    FieldEncodeError(i8),
+    BitmapUnavailable,
+    InvalidMessage,
+    InvalidEdit(String),
+    SectionLengthChanged(u8),
+    InvalidGridFile(String),
+    GridMismatch,
+    EmptySubset,
     FunctionNotImplemented,
     Unknown
 }
@@ -55,7 +83,48 @@ impl ToString for Grib2Error {
                         14 => "Error unpacking Section 7",
                         _ => "unknown"
                     }
-                }
+                },
+                Grib2Error::MessageEncodeError(cdp) => {
+                    match cdp {
+                        -1 => "Tried to use for version other than GRIB Edition 2",
+                        -2 => "GRIB message already complete",
+                        -3 => "Sum of Section byte counts does not add to total byte count",
+                        -4 => "Previous Section was not 7",
+                        _ => "unknown"
+                    }
+                },
+                Grib2Error::GridEncodeError(cdp) => {
+                    match cdp {
+                        -1 => "GRIB message was not initialized",
+                        -2 => "GRIB message already complete",
+                        -3 => "Sum of Section byte counts does not add to total byte count",
+                        -4 => "Previous Section was not 1, 2 or 7",
+                        -5 => "Could not find requested Grid Definition Template",
+                        _ => "unknown"
+                    }
+                },
+                Grib2Error::FieldEncodeError(cdp) => {
+                    match cdp {
+                        -1 => "GRIB message was not initialized",
+                        -2 => "GRIB message already complete",
+                        -3 => "Sum of Section byte counts does not add to total byte count",
+                        -4 => "Previous Section was not 3 or 7",
+                        -5 => "Could not find requested Product Definition Template",
+                        -6 => "Section 3 (GDS) not previously defined in message",
+                        -7 => "Tried to use unsupported Data Representation Template",
+                        -8 => "Specified use of a previously defined bitmap, but one does not exist",
+                        -9 => "GDT of one of 5.50 through 5.53 required to pack using DRT 5.51",
+                        -10 => "Error packing data field",
+                        _ => "unknown"
+                    }
+                },
+                Grib2Error::BitmapUnavailable => "Field uses a bitmap which is not available",
+                Grib2Error::InvalidMessage => "Message sections could not be indexed",
+                Grib2Error::InvalidEdit(_) => "Edited value cannot be changed in an existing message",
+                Grib2Error::SectionLengthChanged(_) => "Edit would change the length of a section, re-encoding is required",
+                Grib2Error::InvalidGridFile(_) => "Grid description file could not be read",
+                Grib2Error::GridMismatch => "Grid coordinates do not describe the grid of the field",
+                Grib2Error::EmptySubset => "No grid points fall inside the requested subset",
                 _ => "unknown"
             }
         )
@@ -160,11 +229,20 @@ pub struct Grib2Field {
     pub data_representation_codepoint: u16,
     pub data_representation_template_len: u32,
     
-    bitmap: Option<Vec<i64>>,
+    bitmap: Option<Bitmap>,
     pub bitmap_indicator: u8,
     
     gridpoint_data: Vec<f32>,
-    pub num_datapoints: u32
+    pub num_datapoints: u32,
+
+    // size in bytes of Sections 5 through 7 as found in the message
+    packed_size: usize,
+
+    // point coordinates of unstructured grids, shared between the fields on the same grid
+    unstructured_coordinates: Option<Arc<UnstructuredGridCoordinates>>,
+
+    // the convention longitudes are given in, once set by convert_longitudes()
+    longitude_convention: Option<LongitudeConvention>
 }
 
 impl Display for Grib2Field {
@@ -223,7 +301,12 @@ gridpoint_data:                         {}
 
 impl Grib2Field {
     unsafe fn new(data: *mut libg2c_sys::gribfield) -> Grib2Field {
-        Grib2Field {
+        let has_bitmap = (*data).ibmap != 255 && !(*data).bmap.is_null();
+
+        // once g2c applies a bitmap, the field holds a value for every grid point
+        let count_values = if has_bitmap && (*data).expanded == 1 { (*data).ngrdpts } else { (*data).ndpts };
+
+        let mut new_field = Grib2Field {
             local: if (*data).locallen > 0 {
                 if let Ok(cstr) = std::ffi::CStr::from_ptr((*data).local as *const i8).to_str() {
                     Some(cstr.to_string())
@@ -260,7 +343,7 @@ impl Grib2Field {
                 .map(|x| *((*data).ipdtmpl.offset(x as isize)))
                 .collect(),
             
-            gridpoint_data: (0..(*data).ndpts)
+            gridpoint_data: (0..count_values)
                 .map(|x| *((*data).fld.offset(x as isize)))
                 .collect(),
             num_datapoints: (*data).ndpts as u32,
@@ -274,10 +357,71 @@ impl Grib2Field {
                 .collect(),
             data_representation_template_len: (*data).idrtlen as u32,
             bitmap_indicator: (*data).ibmap as u8,
-            bitmap: None
+            bitmap: if has_bitmap {
+                    Some(Bitmap::from_flags(std::slice::from_raw_parts((*data).bmap, (*data).ngrdpts as usize)))
+                } else {
+                    None
+                },
+            packed_size: 0,
+            unstructured_coordinates: None,
+            longitude_convention: None
+        };
+
+        new_field.expand_data();
+        new_field
+    }
+
+    // spreads packed values out over the full grid according to the bitmap, missing points are zero
+    fn expand_data(&mut self) {
+        if let Some(bitmap) = self.bitmap.as_ref() {
+            if self.gridpoint_data.len() != bitmap.len() && self.gridpoint_data.len() == bitmap.count_present() {
+                let mut packed = self.gridpoint_data.iter();
+
+                self.gridpoint_data = bitmap
+                    .iter()
+                    .map(|present| if present { *packed.next().unwrap() } else { 0.0 })
+                    .collect();
+                self.expanded = true;
+            }
         }
     }
 
+    /*
+    A copy of this field on another grid, holding the given values (one per grid point, None where
+    missing). Used by the operations which resample or rearrange a field.
+    */
+    pub(crate) fn with_grid(&self, grid_def: u32, grid_template: Vec<i64>, values: Vec<Option<f32>>) -> Grib2Field {
+        let mut field = self.clone();
+
+        field.grd_is_ct31 = true;
+        field.grid_def = grid_def;
+        field.grid_template_len = grid_template.len() as u32;
+        field.grid_template = grid_template;
+        field.grid_arrays_lengths_list = None;
+        field.count_optional_octets_grid = 0;
+        field.interpretation_of_optional_gridpoints = 0;
+        field.count_gridpoints = values.len() as u32;
+        field.unstructured_coordinates = None;
+
+        field.bitmap = if values.iter().all(|value| value.is_some()) {
+            None
+        } else {
+            let mut bitmap = Bitmap::new(values.len());
+            for (point, value) in values.iter().enumerate() {
+                bitmap.set(point, value.is_some());
+            }
+            Some(bitmap)
+        };
+        field.bitmap_indicator = if field.bitmap.is_some() { 0 } else { 255 };
+
+        field.num_datapoints = values.iter().filter(|value| value.is_some()).count() as u32;
+        field.gridpoint_data = values.into_iter().map(|value| value.unwrap_or(0.0)).collect();
+        field.expanded = true;
+        field.packed_size = 0;
+
+        field
+    }
+
     pub fn is_expanded(&self) -> bool {
         self.expanded
     }
@@ -286,10 +430,35 @@ impl Grib2Field {
         self.unpacked
     }
 
-    pub fn bitmap(&self) -> Option<&Vec<i64>> {
+    pub fn bitmap(&self) -> Option<&Bitmap> {
         self.bitmap.as_ref()
     }
 
+    // whether the given grid point holds a value, grid points are counted in file order
+    pub fn is_present(&self, point: usize) -> bool {
+        match self.bitmap.as_ref() {
+            Some(bitmap) => bitmap.get(point),
+            None => point < self.gridpoint_data.len()
+        }
+    }
+
+    // one entry per grid point, None where the bitmap marks the point as missing
+    pub fn data_masked(&self) -> Vec<Option<f32>> {
+        self.gridpoint_data
+            .iter()
+            .enumerate()
+            .map(|(point, value)| if self.is_present(point) { Some(*value) } else { None })
+            .collect()
+    }
+
+    // one entry per grid point, NaN where the bitmap marks the point as missing
+    pub fn data_nan_filled(&self) -> Vec<f32> {
+        self.data_masked()
+            .into_iter()
+            .map(|value| value.unwrap_or(f32::NAN))
+            .collect()
+    }
+
     pub fn data_representation_template_values(&self) -> &Vec<i64> {
         &self.data_representation_template
     }
@@ -303,7 +472,22 @@ impl Grib2Field {
     }
 
     pub fn grid_template_as_geograph_coordinate_grid_definition(&self) -> Option<LatLonGridDefinition> {
-        LatLonGridDefinition::new(self.grid_template())
+        match self.grid() {
+            GridDefinition::LatLon(definition) => Some(definition),
+            _ => None
+        }
+    }
+
+    // interprets the grid template according to the grid definition template number
+    pub fn grid(&self) -> GridDefinition {
+        let mut template = self.grid_template().clone();
+
+        // quasi-regular grids list the number of points in each row after the template
+        if let Some(list) = self.get_grid_arrays_list() {
+            template.extend(list);
+        }
+
+        GridDefinition::new(self.grid_def, &template)
     }
 
     pub fn get_grid_arrays_list(&self) -> Option<&Vec<i64>> {
@@ -329,23 +513,80 @@ impl Grib2Field {
     pub fn empty(&self) -> bool {
        self.gridpoint_data.is_empty()
     }
+
+    pub fn packed_size(&self) -> usize {
+        self.packed_size
+    }
+
+    /*
+    Attaches coordinates loaded from a grid description file to a field on an unstructured grid
+    (template 3.101). The coordinates must have the same UUID as the grid and one point per
+    grid point.
+    */
+    pub fn attach_unstructured_coordinates(&mut self, coordinates: Arc<UnstructuredGridCoordinates>) -> Result<(), Grib2Error> {
+        match self.grid() {
+            GridDefinition::Unstructured(grid) if coordinates.describes(&grid, self.count_gridpoints as usize) => {
+                self.unstructured_coordinates = Some(coordinates);
+                Ok(())
+            },
+            GridDefinition::Unstructured(_) => Err(Grib2Error::GridMismatch),
+            _ => Err(Grib2Error::FunctionNotImplemented)
+        }
+    }
+
+    pub fn unstructured_coordinates(&self) -> Option<&Arc<UnstructuredGridCoordinates>> {
+        self.unstructured_coordinates.as_ref()
+    }
+
+    /*
+    The data reordered into north to south rows of west to east points (see ScanningMode).
+    None for grids without a fixed number of points per row.
+    */
+    pub fn data_canonical(&self) -> Option<Vec<f32>> {
+        let grid = self.grid();
+        let (ni, nj) = grid.grid_shape()?;
+
+        grid.scanning_mode()?.to_canonical(&self.gridpoint_data, ni, nj)
+    }
+
+    // latitudes and longitudes of the grid points, from attached coordinates for unstructured grids
+    pub fn coordinates(&self) -> Option<GridCoordinates> {
+        match (self.grid(), self.unstructured_coordinates.as_ref()) {
+            (GridDefinition::Unstructured(_), Some(coordinates)) => Some(GridCoordinates::Points {
+                shape: None,
+                latitudes: coordinates.latitudes().clone(),
+                longitudes: coordinates.longitudes().clone()
+            }),
+            (grid, _) => match (grid.coordinates()?, self.longitude_convention) {
+                (GridCoordinates::Axes { latitudes, longitudes }, Some(convention)) => Some(GridCoordinates::Axes {
+                    latitudes,
+                    longitudes: longitudes.iter().map(|lon| convention.normalise(*lon)).collect()
+                }),
+                (coordinates, _) => Some(coordinates)
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Grib2Message {
     info: Result<Grib2Info, Grib2Error>,
     fields: Vec<Grib2Field>,
-    errors: Vec<(usize, Grib2Error)>
+    errors: Vec<(usize, Grib2Error)>,
+    raw: Vec<u8>
 }
 
 impl Grib2Message {
-    unsafe fn new(grib_data: *mut u8) -> Result<Grib2Message, Grib2Error> {
+    unsafe fn new(grib_data: *mut u8, length: usize, predefined_bitmaps: &BitmapRegistry) -> Result<Grib2Message, Grib2Error> {
         let mut new_inst = Grib2Message {
             info: Err(Grib2Error::Unknown),
             fields: Vec::new(),
-            errors: Vec::new()
+            errors: Vec::new(),
+            raw: std::slice::from_raw_parts(grib_data, length).to_vec()
         };
 
+        let index = sections::SectionIndex::new(&new_inst.raw);
+
         // declare memory layouts for Section 0 and Section 1 of the GRIB2 file
         let sec0buf_layout = Layout::from_size_align(3 * size_of::<i64>(), 1).unwrap();
         let sec1buf_layout = Layout::from_size_align(13 * size_of::<i64>(), 1).unwrap();
@@ -359,6 +600,9 @@ impl Grib2Message {
         let mut count_fields: i64 = 0;
         let mut count_locals: i64 = 0;
 
+        // the last bitmap defined in this message, for fields with bitmap indicator 254
+        let mut previous_bitmap: Option<Bitmap> = None;
+
         let info_err = libg2c_sys::g2_info(
             grib_data, 
             sec0buf as *mut i64, 
@@ -379,7 +623,36 @@ impl Grib2Message {
             let field_error = libg2c_sys::g2_getfld(grib_data, i + 1, 1, 1, &mut field);
     
             if field_error == 0 {
-                new_inst.fields.push(Grib2Field::new(field))
+                let mut new_field = Grib2Field::new(field);
+
+                match new_field.bitmap_indicator {
+                    0 => previous_bitmap = new_field.bitmap.clone(),
+                    254 => if new_field.bitmap.is_none() {
+                        new_field.bitmap = previous_bitmap.clone();
+                    },
+                    255 => (),
+                    predefined => if let (None, Ok(info)) = (new_field.bitmap.as_ref(), new_inst.info.as_ref()) {
+                        new_field.bitmap = predefined_bitmaps.get(info.center, predefined).cloned();
+                    }
+                }
+
+                // a bitmap only applies if it covers the whole grid
+                if new_field.bitmap.as_ref().is_some_and(|b| b.len() != new_field.count_gridpoints as usize) {
+                    new_field.bitmap = None;
+                }
+
+                new_field.expand_data();
+
+                // record how large the packed data is, used when reporting on repacking
+                if let Some(secs) = index.as_ref().and_then(|idx| idx.field_sections(i as usize)) {
+                    new_field.packed_size = secs
+                        .iter()
+                        .filter(|s| s.number >= 5)
+                        .map(|s| s.length)
+                        .sum();
+                }
+
+                new_inst.fields.push(new_field)
             } else {
                 new_inst.errors.push((i as usize, Grib2Error::FieldReadError(field_error as u8)))
             }
@@ -390,6 +663,33 @@ impl Grib2Message {
         Ok(new_inst)
     }
 
+    // builds a message from a buffer holding exactly one complete GRIB2 message
+    pub fn from_bytes(raw: Vec<u8>) -> Result<Grib2Message, Grib2Error> {
+        Grib2Message::from_bytes_with_bitmaps(raw, &BitmapRegistry::new())
+    }
+
+    // as from_bytes, resolving predefined bitmaps (indicators 1 through 253) from the registry
+    pub fn from_bytes_with_bitmaps(mut raw: Vec<u8>, predefined_bitmaps: &BitmapRegistry) -> Result<Grib2Message, Grib2Error> {
+        unsafe {
+            Grib2Message::new(raw.as_mut_ptr(), raw.len(), predefined_bitmaps)
+        }
+    }
+
+    // carries predefined bitmaps over from the message this one was derived from
+    fn inherit_predefined_bitmaps(&mut self, from: &Grib2Message) {
+        for (field, original) in self.fields.iter_mut().zip(from.fields.iter()) {
+            if field.bitmap.is_none() && field.bitmap_indicator == original.bitmap_indicator {
+                field.bitmap = original.bitmap.clone();
+                field.expand_data();
+            }
+        }
+    }
+
+    // the encoded message, exactly as it would be written to a file
+    pub fn bytes(&self) -> &Vec<u8> {
+        &self.raw
+    }
+
     pub fn info(&self) -> Result<&Grib2Info, &Grib2Error> {
         self.info.as_ref()
     }
@@ -401,12 +701,21 @@ impl Grib2Message {
     pub fn errors(&self) -> &Vec<(usize, Grib2Error)> {
         self.errors.as_ref()
     }
+
+    // attaches the coordinates to every field they describe, returning how many fields took them
+    pub fn attach_unstructured_coordinates(&mut self, coordinates: &Arc<UnstructuredGridCoordinates>) -> usize {
+        self.fields
+            .iter_mut()
+            .filter_map(|field| field.attach_unstructured_coordinates(Arc::clone(coordinates)).ok())
+            .count()
+    }
 }
 
 pub struct Grib2 {
     path: String,
     messages: Vec<Grib2Message>,
-    errors: Vec<(usize, Grib2Error)>
+    errors: Vec<(usize, Grib2Error)>,
+    predefined_bitmaps: BitmapRegistry
 }
 
 impl Grib2 {
@@ -418,10 +727,20 @@ impl Grib2 {
         Grib2 {
             path: path,
             messages: Vec::new(),
-            errors: Vec::new()
+            errors: Vec::new(),
+            predefined_bitmaps: BitmapRegistry::new()
         }
     }
 
+    // predefined bitmaps must be registered before calling read_all() to be applied
+    pub fn register_predefined_bitmap(&mut self, center: u16, indicator: u8, bitmap: Bitmap) -> Option<Bitmap> {
+        self.predefined_bitmaps.register(center, indicator, bitmap)
+    }
+
+    pub fn predefined_bitmaps(&self) -> &BitmapRegistry {
+        &self.predefined_bitmaps
+    }
+
     pub fn messages(&self) -> &Vec<Grib2Message> {
         self.messages.as_ref()
     }
@@ -520,7 +839,7 @@ impl Grib2 {
                 gmsg_end = gmsg_begin + gmsg_prim_length;
 
                 // attempt to create new message
-                let maybe_msg = Grib2Message::new(gribmsg);
+                let maybe_msg = Grib2Message::new(gribmsg, gmsg_prim_length as usize, &self.predefined_bitmaps);
                 if let Err(why) = maybe_msg { // if we failed
                     // stow the error
                     self.errors.push((count, why))
@@ -546,6 +865,59 @@ impl Grib2 {
 
 mod tests {
 
+    // a template 3.0 grid of ni by nj points one degree apart, from 90N 0E going south and east
+    #[cfg(test)]
+    fn latlon_template(ni: i64, nj: i64) -> Vec<i64> {
+        vec![6, 0, 0, 0, 0, 0, 0, ni, nj, 0, 0, 90000000, 0, 48, 90000000 - (nj - 1) * 1000000, (ni - 1) * 1000000, 1000000, 1000000, 0]
+    }
+
+    /*
+    Encodes a message on the given grid holding one simply packed field for each entry of
+    `fields`, given as (bitmap indicator, bitmap flags, values of every grid point).
+    */
+    #[cfg(test)]
+    fn encode_message(grid_number: i64, grid_template: &[i64], row_counts: &[i64], fields: &[(i64, &[i64], &[f32])]) -> Vec<u8> {
+        let point_count = fields.first().map_or(0, |field| field.2.len());
+
+        let mut sec0: Vec<i64> = vec![0, 2];
+        let mut sec1: Vec<i64> = vec![7, 0, 2, 1, 1, 2020, 5, 6, 12, 0, 0, 0, 1];
+        let mut igds: Vec<i64> = vec![0, point_count as i64, if row_counts.is_empty() { 0 } else { 2 }, if row_counts.is_empty() { 0 } else { 1 }, grid_number];
+        let mut grid_template = grid_template.to_vec();
+        let mut row_counts = row_counts.to_vec();
+        let mut buffer: Vec<u8> = vec![0; 4096 + 8 * point_count * fields.len()];
+
+        unsafe {
+            assert!(libg2c_sys::g2_create(buffer.as_mut_ptr(), sec0.as_mut_ptr(), sec1.as_mut_ptr()) > 0);
+            assert!(libg2c_sys::g2_addgrid(buffer.as_mut_ptr(), igds.as_mut_ptr(), grid_template.as_mut_ptr(), row_counts.as_mut_ptr(), row_counts.len() as i64) > 0);
+
+            for (bitmap_indicator, bitmap, values) in fields {
+                let mut prod_template: Vec<i64> = vec![0, 0, 2, 0, 96, 0, 0, 1, 6, 103, 0, 2, 255, 0, 0];
+                let mut drt: Vec<i64> = vec![0, 0, 3, 0, 0];
+                let mut data = values.to_vec();
+                let mut bitmap = bitmap.to_vec();
+
+                assert!(libg2c_sys::g2_addfield(
+                    buffer.as_mut_ptr(),
+                    0,
+                    prod_template.as_mut_ptr(),
+                    std::ptr::null_mut(),
+                    0,
+                    0,
+                    drt.as_mut_ptr(),
+                    data.as_mut_ptr(),
+                    values.len() as i64,
+                    *bitmap_indicator,
+                    if bitmap.is_empty() { std::ptr::null_mut() } else { bitmap.as_mut_ptr() }) > 0);
+            }
+
+            let length = libg2c_sys::g2_gribend(buffer.as_mut_ptr());
+            assert!(length > 0);
+            buffer.truncate(length as usize);
+        }
+
+        buffer
+    }
+
     #[test]
     fn grib2_new() {
         let g2 = crate::Grib2::new(String::from("test_path"));
@@ -553,6 +925,769 @@ mod tests {
         assert_eq!(g2.path().as_str(), "test_path");
     }
 
+    #[test]
+    fn bitmap_from_flags() {
+        let flags = vec![1, 0, 0, 1, 1, 1, 0, 1, 0, 1];
+        let bitmap = crate::bitmap::Bitmap::from_flags(&flags);
+
+        assert_eq!(bitmap.len(), 10);
+        assert_eq!(bitmap.count_present(), 6);
+        assert_eq!(bitmap.to_flags(), flags);
+        assert!(!bitmap.get(10));
+
+        assert_eq!(crate::bitmap::Bitmap::new(10).count_present(), 10);
+    }
+
+    #[test]
+    fn lambert_conformal_hrrr_corners() {
+        // the HRRR CONUS grid
+        let hrrr = crate::grid::LambertConformalGridDefinition::new(&vec![
+            6, 0, 0, 0, 0, 0, 0, 1799, 1059, 21138123, 237280472, 8, 38500000, 262500000,
+            3000000, 3000000, 0, 64, 38500000, 38500000, -90000000, 0
+        ]).unwrap();
+
+        let first = hrrr.latlon(0.0, 0.0);
+        assert!((first.0 - 21.138123).abs() < 1e-6 && (first.1 + 122.719528).abs() < 1e-6);
+
+        let last = hrrr.latlon(1798.0, 1058.0);
+        assert!((last.0 - 47.842195).abs() < 1e-3 && (last.1 + 60.917193).abs() < 1e-3);
+
+        let index = hrrr.grid_index(last.0, last.1);
+        assert!((index.0 - 1798.0).abs() < 1e-6 && (index.1 - 1058.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn latlon_crs() {
+        let grid = crate::grid::LatLonGridDefinition::new(&latlon_template(360, 181)).unwrap();
+
+        assert_eq!(grid.proj_string(), "+proj=longlat +R=6371229 +no_defs");
+        assert_eq!(grid.geotransform().unwrap(), [-0.5, 1.0, 0.0, 90.5, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn lambert_conformal_hrrr_crs() {
+        let hrrr = crate::grid::LambertConformalGridDefinition::new(&vec![
+            6, 0, 0, 0, 0, 0, 0, 1799, 1059, 21138123, 237280472, 8, 38500000, 262500000,
+            3000000, 3000000, 0, 64, 38500000, 38500000, -90000000, 0
+        ]).unwrap();
+
+        assert_eq!(hrrr.proj_string(), "+proj=lcc +lat_0=38.5 +lon_0=-97.5 +lat_1=38.5 +lat_2=38.5 +x_0=0 +y_0=0 +R=6371229 +units=m +no_defs");
+
+        // GDAL places the corner of the first cell at (-2699020.14, -1588806.15) m
+        let transform = hrrr.geotransform().unwrap();
+        let expected = [-2699020.14, 3000.0, 0.0, -1588806.15, 0.0, 3000.0];
+        assert!(transform.iter().zip(expected.iter()).all(|(value, expected)| (value - expected).abs() < 1.0));
+
+        // the origin of the CRS is LaD on LoV
+        let (i, j) = hrrr.grid_index(38.5, 262.5);
+        let x = transform[0] + (i + 0.5) * transform[1] + (j + 0.5) * transform[2];
+        let y = transform[3] + (i + 0.5) * transform[4] + (j + 0.5) * transform[5];
+        assert!(x.abs() < 1e-3 && y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn polar_stereographic_round_trip() {
+        // 100 km grids true at 60 degrees, over each pole
+        for (centre, la1, lov) in [(0x00, 30000000i64, 255000000), (0x80, -30000000, 100000000)].iter() {
+            let grid = crate::grid::PolarStereographicGridDefinition::new(&vec![
+                6, 0, 0, 0, 0, 0, 0, 80, 80, *la1, 250000000, 8, 60000000 * la1.signum(), *lov,
+                100000000, 100000000, *centre, 64
+            ]).unwrap();
+
+            let first = grid.latlon(0.0, 0.0);
+            assert!((first.0 - *la1 as f64 * 1e-6).abs() < 1e-9 && (first.1 + 110.0).abs() < 1e-9);
+
+            for (i, j) in [(0.0, 0.0), (40.0, 40.0), (79.0, 12.5), (3.25, 71.0)].iter() {
+                let (lat, lon) = grid.latlon(*i, *j);
+                let index = grid.grid_index(lat, lon);
+                assert!((index.0 - i).abs() < 1e-6 && (index.1 - j).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn mercator_round_trip() {
+        // 20 km cells true at 20N, from 10S 100E to 30N 160E
+        let grid = crate::grid::MercatorGridDefinition::new(&vec![
+            6, 0, 0, 0, 0, 0, 0, 300, 230, -10000000, 100000000, 48, 20000000, 30000000, 160000000,
+            64, 0, 20000000, 20000000
+        ]).unwrap();
+
+        let first = grid.latlon(0.0, 0.0);
+        assert!((first.0 + 10.0).abs() < 1e-9 && (first.1 - 100.0).abs() < 1e-9);
+
+        // rows of a Mercator grid run along parallels
+        assert!((grid.latlon(150.0, 7.0).0 - grid.latlon(0.0, 7.0).0).abs() < 1e-9);
+
+        for (i, j) in [(0.0, 0.0), (150.0, 115.0), (299.0, 3.5), (12.25, 229.0)].iter() {
+            let (lat, lon) = grid.latlon(*i, *j);
+            let index = grid.grid_index(lat, lon);
+            assert!((index.0 - i).abs() < 1e-6 && (index.1 - j).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn rotated_pole_round_trip() {
+        // 0.5 degree rotated grid around the rotated origin, southern pole at 30S 15E
+        let grid = crate::grid::RotatedLatLonGridDefinition::new(&vec![
+            6, 0, 0, 0, 0, 0, 0, 41, 41, 0, 0, -10000000, 350000000, 48, 10000000, 10000000,
+            500000, 500000, 64, -30000000, 15000000, 0
+        ]).unwrap();
+
+        // the rotated origin lies on the meridian of the pole, 90 degrees from it
+        let centre = grid.latlon(20.0, 20.0);
+        assert!((centre.0 - 60.0).abs() < 1e-9 && (centre.1 - 15.0).abs() < 1e-9);
+
+        for (i, j) in [(0.0, 0.0), (20.0, 20.0), (40.0, 3.5), (7.25, 40.0)].iter() {
+            let (lat, lon) = grid.latlon(*i, *j);
+            let index = grid.grid_index(lat, lon);
+            assert!((index.0 - i).abs() < 1e-6 && (index.1 - j).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn geostationary_round_trip() {
+        // the full disk of a satellite over 0E, with the sub-satellite point at grid point (1856, 1856)
+        let grid = crate::grid::SpaceViewGridDefinition::new(&vec![
+            5, 0, 0, 0, 0, 0, 0, 3712, 3712, 0, 0, 48, 3622, 3610, 1856000, 1856000, 64, 0, 6610700, 0, 0
+        ]).unwrap();
+
+        let nadir = grid.latlon(1856.0, 1856.0).unwrap();
+        assert!(nadir.0.abs() < 1e-9 && nadir.1.abs() < 1e-9);
+
+        // the corners of the image look past the earth
+        assert!(grid.latlon(0.0, 0.0).is_none());
+        assert!(grid.grid_index(0.0, 180.0).is_none());
+
+        for (i, j) in [(1856.0, 1856.0), (1000.0, 2500.0), (3000.0, 1200.5), (400.25, 1856.0)].iter() {
+            let (lat, lon) = grid.latlon(*i, *j).unwrap();
+            let index = grid.grid_index(lat, lon).unwrap();
+            assert!((index.0 - i).abs() < 1e-6 && (index.1 - j).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn unstructured_coordinates_match_uuid() {
+        let grid = crate::grid::UnstructuredGridDefinition::new(&[
+            6, 1, 1, 0x12345678, 0x9abcdef0, 0x0fedcba9, 0x87654321
+        ]).unwrap();
+        assert_eq!(grid.uuid_string(), "12345678-9abc-def0-0fed-cba987654321");
+
+        let other_uuid = [0u8; 16];
+        let latitudes = vec![10.0, 20.0, 30.0];
+        let longitudes = vec![5.0, 15.0, 25.0];
+
+        let coordinates = crate::grid::UnstructuredGridCoordinates::new(grid.uuid, latitudes.clone(), longitudes.clone()).unwrap();
+        let other = crate::grid::UnstructuredGridCoordinates::new(other_uuid, latitudes, longitudes).unwrap();
+
+        assert!(coordinates.describes(&grid, 3));
+        assert!(!coordinates.describes(&grid, 4));
+        assert!(!other.describes(&grid, 3));
+    }
+
+    #[test]
+    fn unstructured_coordinates_from_csv() {
+        let grid = crate::grid::UnstructuredGridDefinition::new(&[
+            6, 1, 1, 0x12345678, 0x9abcdef0, 0x0fedcba9, 0x87654321
+        ]).unwrap();
+
+        let write = |name: &str, contents: &str| {
+            let path = std::env::temp_dir().join(format!("grib2-{}-{}.csv", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+
+        let matching = write("matching", "uuid,12345678-9abc-def0-0fed-cba987654321\nlat,lon\n10.0,5.0\n20.0,15.0\n30.0,25.0\n");
+        let mismatched = write("mismatched", "uuid,00000000000000000000000000000000\nlatitude,longitude\n10.0,5.0\n20.0,15.0\n30.0,25.0\n");
+        let unnamed = write("unnamed", "lat,lon\n10.0,5.0\n");
+
+        let coordinates = crate::grid::UnstructuredGridCoordinates::from_csv(&matching).unwrap();
+        assert_eq!(coordinates.uuid, grid.uuid);
+        assert_eq!(coordinates.latlon(2), Some((30.0, 25.0)));
+        assert!(coordinates.describes(&grid, 3));
+
+        let other = crate::grid::UnstructuredGridCoordinates::from_csv(&mismatched).unwrap();
+        assert_eq!(other.len(), 3);
+        assert!(!other.describes(&grid, 3));
+
+        assert!(matches!(
+            crate::grid::UnstructuredGridCoordinates::from_csv(&unnamed),
+            Err(crate::Grib2Error::InvalidGridFile(_))
+        ));
+
+        for path in [matching, mismatched, unnamed].iter() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn grid_definition_dispatch() {
+        use crate::grid::{ArakawaStaggering, GridDefinition};
+
+        // long enough for any of the structured templates, the values themselves do not matter here
+        let structured: Vec<i64> = (1..=30).collect();
+        let unstructured: Vec<i64> = vec![6, 1, 1, 0x12345678, 0x9abcdef0, 0x0fedcba9, 0x87654321];
+
+        let cases: [(u32, &Vec<i64>); 11] = [
+            (0, &structured), (1, &structured), (10, &structured), (20, &structured), (30, &structured),
+            (40, &structured), (90, &structured), (101, &unstructured), (32768, &structured), (32769, &structured),
+            (50, &structured)
+        ];
+
+        for (number, template) in cases.iter() {
+            let grid = GridDefinition::new(*number, template);
+            let matched = match number {
+                0 => matches!(grid, GridDefinition::LatLon(_)),
+                1 => matches!(grid, GridDefinition::RotatedLatLon(_)),
+                10 => matches!(grid, GridDefinition::Mercator(_)),
+                20 => matches!(grid, GridDefinition::PolarStereographic(_)),
+                30 => matches!(grid, GridDefinition::LambertConformal(_)),
+                40 => matches!(grid, GridDefinition::Gaussian(_)),
+                90 => matches!(grid, GridDefinition::SpaceView(_)),
+                101 => matches!(grid, GridDefinition::Unstructured(_)),
+                32768 => matches!(&grid, GridDefinition::ArakawaRotatedLatLon(def) if def.staggering == ArakawaStaggering::E),
+                32769 => matches!(&grid, GridDefinition::ArakawaRotatedLatLon(def) if def.staggering == ArakawaStaggering::B),
+                _ => matches!(&grid, GridDefinition::Unsupported { number: 50, raw } if raw == *template)
+            };
+
+            assert!(matched, "template 3.{} read as the wrong variant", number);
+            assert_eq!(grid.template_number(), *number);
+            assert_eq!(grid.is_supported(), *number != 50);
+        }
+
+        // a supported template too short to read is kept as it came
+        let short: Vec<i64> = vec![6, 0, 0];
+        assert!(matches!(GridDefinition::new(30, &short), GridDefinition::Unsupported { number: 30, raw } if raw == short));
+
+        // only template 3.0 reads as a geographic coordinate grid
+        let values: Vec<f32> = vec![0.0; 25];
+        let polar: Vec<i64> = vec![6, 0, 0, 0, 0, 0, 0, 5, 5, -60000000, 30000000, 8, -60000000, 100000000, 30000000, 30000000, 0x80, 64];
+        let latlon = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(5, 5), &[], &[(255, &[], &values)])).unwrap();
+        let other = crate::Grib2Message::from_bytes(encode_message(20, &polar, &[], &[(255, &[], &values)])).unwrap();
+
+        assert!(latlon.fields()[0].grid_template_as_geograph_coordinate_grid_definition().is_some());
+        assert!(other.fields()[0].grid_template_as_geograph_coordinate_grid_definition().is_none());
+    }
+
+    #[test]
+    fn earth_models_from_code_table_3_2() {
+        use crate::earth::EarthModel;
+
+        // (shape, radius factor and value, major axis factor and value, minor axis factor and value, semi-major axis, semi-minor axis)
+        let cases = [
+            (0, 0, 0, 0, 0, 0, 0, 6367470.0, 6367470.0),
+            (1, 2, 637122900, 0, 0, 0, 0, 6371229.0, 6371229.0),
+            (2, 0, 0, 0, 0, 0, 0, 6378160.0, 6356775.0),
+            (3, 0, 0, 3, 6378137, 7, 63567523142, 6378137.0, 6356752.3142),
+            (4, 0, 0, 0, 0, 0, 0, 6378137.0, 6356752.314140),
+            (5, 0, 0, 0, 0, 0, 0, 6378137.0, 6356752.314245),
+            (6, 0, 0, 0, 0, 0, 0, 6371229.0, 6371229.0),
+            (7, 0, 0, 1, 63781370, 1, 63567523, 6378137.0, 6356752.3),
+            (8, 0, 0, 0, 0, 0, 0, 6371200.0, 6371200.0),
+            (9, 0, 0, 0, 0, 0, 0, 6377563.396, 6356256.909),
+            (10, 0, 0, 0, 0, 0, 0, 6371229.0, 6371229.0),
+            (255, 0, 0, 0, 0, 0, 0, 6371229.0, 6371229.0)
+        ];
+
+        for (shape, rf, rv, af, av, bf, bv, major, minor) in cases.iter() {
+            let earth = EarthModel::from_template(*shape, *rf, *rv, *af, *av, *bf, *bv);
+            assert!((earth.semi_major_axis() - major).abs() < 1e-6, "shape {}", shape);
+            assert!((earth.semi_minor_axis() - minor).abs() < 1e-6, "shape {}", shape);
+        }
+    }
+
+    #[test]
+    fn gaussian_latitudes_n48() {
+        let latitudes = crate::grid::gaussian_latitudes(48);
+
+        assert_eq!(latitudes.len(), 96);
+        assert!((latitudes[0] - 88.572169).abs() < 1e-6);
+        assert!((latitudes[47] + latitudes[48]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gaussian_grid_round_trip() {
+        // a global N48 grid from the north, and a regional one scanning from the south
+        let global = crate::grid::GridDefinition::new(40, &vec![
+            6, 0, 0, 0, 0, 0, 0, 192, 96, 0, 0, 88572169, 0, 48, -88572169, 358125000, 1875000, 48, 0
+        ]);
+        let regional = crate::grid::GridDefinition::new(40, &vec![
+            6, 0, 0, 0, 0, 0, 0, 40, 20, 0, 0, -20000000, 10000000, 48, 20000000, 83125000, 1875000, 48, 64
+        ]);
+
+        for grid in [global, regional].iter() {
+            let (ni, nj) = grid.grid_shape().unwrap();
+
+            for (i, j) in [(0, 0), (ni / 2, nj / 2), (ni - 1, 3), (7, nj - 1)].iter() {
+                let (lat, lon) = grid.latlon(*i as f64, *j as f64).unwrap();
+                let (index_i, index_j) = grid.grid_index(lat, lon).unwrap();
+                assert!((index_i - *i as f64).abs() < 1e-6 && (index_j - *j as f64).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn scanning_mode_canonical_round_trip() {
+        // 3 by 2 grid scanned south to north, west to east, alternating direction by row
+        let mode = crate::grid::ScanningMode::from_flags(0x50);
+        let stored = vec![3, 4, 5, 2, 1, 0];
+
+        let canonical = mode.to_canonical(&stored, 3, 2).unwrap();
+        assert_eq!(canonical, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(mode.from_canonical(&canonical, 3, 2).unwrap(), stored);
+        assert_eq!(mode.to_flags(), 0x50);
+
+        for (index, (i, j)) in mode.point_order(3, 2).enumerate() {
+            assert_eq!(mode.grid_index(index, 3, 2), (i, j));
+        }
+    }
+
+    #[test]
+    fn repack_round_trip() {
+        let values: Vec<f32> = (0..12).map(|value| value as f32 * 0.25 - 1.0).collect();
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(4, 3), &[], &[(255, &[], &values)])).unwrap();
+
+        // only the leading values of the complex packing template are given, g2c reads all 18
+        let (repacked, report) = message.repack(3, &[0, 0, 2]).unwrap();
+        let decoded = crate::Grib2Message::from_bytes(repacked.bytes().clone()).unwrap();
+
+        let (before, after) = (&message.fields()[0], &decoded.fields()[0]);
+        assert_eq!(after.data_representation_codepoint, 3);
+        assert_eq!(after.grid_template(), before.grid_template());
+        assert_eq!(after.prod_template_values(), before.prod_template_values());
+        assert!(report.max_abs_error <= 0.005);
+
+        for (a, b) in before.data().iter().zip(after.data().iter()) {
+            assert!((a - b).abs() <= 0.005);
+        }
+    }
+
+    #[test]
+    fn product_definition_edit_round_trip() {
+        let values: Vec<f32> = (0..12).map(|value| value as f32).collect();
+        let mut message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(4, 3), &[], &[(255, &[], &values)])).unwrap();
+        let (length, data) = (message.bytes().len(), message.fields()[0].data().clone());
+
+        let mut definition = message.fields()[0].prod_template_as_horizontal_layer_definition().unwrap();
+        definition.forecast_time = 36;
+        definition.first_fixed_surface_scale_value = 10;
+
+        message.set_product_definition(0, &definition).unwrap();
+
+        let edited = &message.fields()[0];
+        assert_eq!(message.bytes().len(), length);
+        assert!(edited.prod_template_as_horizontal_layer_definition().unwrap() == definition);
+        assert_eq!(edited.grid_template(), &latlon_template(4, 3));
+        assert_eq!(edited.data(), &data);
+    }
+
+    #[test]
+    fn identification_edit_round_trip() {
+        let values: Vec<f32> = (0..12).map(|value| value as f32).collect();
+        let mut message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(4, 3), &[], &[(255, &[], &values)])).unwrap();
+        let (length, data) = (message.bytes().len(), message.fields()[0].data().clone());
+
+        let mut info = message.info().unwrap().clone();
+        info.center = 98;
+        info.subcenter = 3;
+        info.ref_time_significance = 0;
+        info.ref_time += chrono::Duration::hours(6);
+        info.prod_status = 2;
+        info.data_type = 2;
+        info.discipline = 10;
+
+        message.set_info(&info, false).unwrap();
+
+        let edited = message.info().unwrap();
+        assert!(*edited == info);
+        assert_eq!(chrono::Timelike::hour(&edited.ref_time), 18);
+        assert_eq!(message.bytes().len(), length);
+        assert_eq!(message.fields()[0].data(), &data);
+
+        let mut edition = info.clone();
+        edition.grib_edition = 1;
+        assert!(matches!(message.set_info(&edition, false), Err(crate::Grib2Error::InvalidEdit(_))));
+    }
+
+    #[test]
+    fn product_template_edit_changing_length() {
+        let values: Vec<f32> = (0..12).map(|value| value as f32).collect();
+        let mut message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(4, 3), &[], &[(255, &[], &values)])).unwrap();
+        let (length, data) = (message.bytes().len(), message.fields()[0].data().clone());
+
+        // 4.8, the hourly accumulation ending an hour after the reference time
+        let accumulation: Vec<i64> = vec![
+            0, 0, 2, 0, 96, 0, 0, 1, 0, 103, 0, 2, 255, 0, 0,
+            2020, 5, 6, 13, 0, 0, 1, 0, 1, 2, 1, 1, 1, 0
+        ];
+
+        assert!(matches!(message.set_prod_template(0, 8, &accumulation, false), Err(crate::Grib2Error::SectionLengthChanged(4))));
+        assert_eq!(message.bytes().len(), length);
+        assert_eq!(message.fields()[0].prod_codepoint, 0);
+
+        message.set_prod_template(0, 8, &accumulation, true).unwrap();
+
+        let edited = &message.fields()[0];
+        assert_eq!(edited.prod_codepoint, 8);
+        assert_eq!(edited.prod_template_values(), &accumulation);
+        // Section 4 grows from 34 to 58 octets
+        assert_eq!(message.bytes().len(), length + 24);
+        assert_eq!(edited.data(), &data);
+    }
+
+    #[test]
+    fn bitmap_reused_by_later_field() {
+        let flags: Vec<i64> = vec![1, 0, 1, 1, 0, 1];
+        let values: Vec<f32> = vec![1.0, 0.0, 3.0, 4.0, 0.0, 6.0];
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(3, 2), &[], &[
+            (0, &flags, &values),
+            (254, &flags, &values)
+        ])).unwrap();
+
+        let reused = &message.fields()[1];
+        assert_eq!(reused.bitmap_indicator, 254);
+        assert_eq!(reused.bitmap().unwrap().to_flags(), flags);
+        assert_eq!(reused.data_masked(), message.fields()[0].data_masked());
+        assert_eq!(reused.data_masked().iter().filter(|value| value.is_none()).count(), 2);
+    }
+
+    #[test]
+    fn predefined_bitmap_per_center() {
+        // bitmap 5 of center 7, the center encode_message writes into Section 1
+        let flags: Vec<i64> = vec![1, 0, 1, 1, 0, 1];
+        let values: Vec<f32> = vec![1.0, 0.0, 3.0, 4.0, 0.0, 6.0];
+
+        let path = std::env::temp_dir().join(format!("grib2-{}-predefined.grib2", std::process::id()));
+        std::fs::write(&path, encode_message(0, &latlon_template(3, 2), &[], &[(5, &[], &values)])).unwrap();
+
+        let read = |registered: Option<u16>| {
+            let mut grib = crate::Grib2::new(path.to_string_lossy().to_string());
+            if let Some(center) = registered {
+                grib.register_predefined_bitmap(center, 5, crate::bitmap::Bitmap::from_flags(&flags));
+            }
+
+            assert_eq!(grib.read_all(), Some(1));
+            grib.messages()[0].fields()[0].clone()
+        };
+
+        let field = read(Some(7));
+        assert_eq!(field.bitmap_indicator, 5);
+        assert_eq!(field.bitmap().unwrap().to_flags(), flags);
+        assert_eq!(field.data_masked().iter().filter(|value| value.is_none()).count(), 2);
+        assert!((field.data_masked()[5].unwrap() - 6.0).abs() < 0.005);
+
+        // nothing registered, or only for another center
+        for registered in [None, Some(8)].iter() {
+            let field = read(*registered);
+            assert!(field.bitmap().is_none());
+            assert!(matches!(field.repack(3, &[0, 0, 2]), Err(crate::Grib2Error::BitmapUnavailable)));
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn value_at_masked_point() {
+        // rows at 50N and 49N, points at 10E, 11E and 12E, well away from the pole
+        let template: Vec<i64> = vec![6, 0, 0, 0, 0, 0, 0, 3, 2, 0, 0, 50000000, 10000000, 48, 49000000, 12000000, 1000000, 1000000, 0];
+        let flags: Vec<i64> = vec![1, 0, 1, 1, 0, 1];
+        let values: Vec<f32> = vec![1.0, 0.0, 3.0, 4.0, 0.0, 6.0];
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &template, &[], &[
+            (0, &flags, &values)
+        ])).unwrap();
+        let field = &message.fields()[0];
+
+        // nearest to the masked point at 50N 11E: its own index and coordinates, no value
+        let missing = field.value_at(49.8, 11.3).unwrap();
+        assert_eq!((missing.index, missing.value), (1, None));
+        assert!((missing.latitude - 50.0).abs() < 1e-9 && (missing.longitude - 11.0).abs() < 1e-9);
+
+        // the next point along the row is present
+        let next = field.value_at(49.8, 11.7).unwrap();
+        assert_eq!(next.index, 2);
+        assert!((next.latitude - 50.0).abs() < 1e-9 && (next.longitude - 12.0).abs() < 1e-9);
+        assert!((next.value.unwrap() - 3.0).abs() < 0.005);
+
+        let present = field.value_at(49.1, 11.8).unwrap();
+        assert_eq!(present.index, 5);
+        assert!((present.latitude - 49.0).abs() < 1e-9 && (present.longitude - 12.0).abs() < 1e-9);
+        assert!((present.value.unwrap() - 6.0).abs() < 0.005);
+
+        // outside the grid, and not a position at all
+        assert!(field.value_at(40.0, 11.0).is_none());
+        assert!(field.value_at(f64::NAN, f64::NAN).is_none());
+    }
+
+    #[test]
+    fn interpolation_reproduces_linear_fields() {
+        // 2i + 3j on an 8 by 6 grid, from 90N 0E going south and east one degree at a time
+        let values: Vec<f32> = (0..48).map(|point| (2 * (point % 8) + 3 * (point / 8)) as f32).collect();
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(8, 6), &[], &[
+            (255, &[], &values)
+        ])).unwrap();
+        let field = &message.fields()[0];
+
+        for (lat, lon) in [(87.3, 3.4), (88.5, 2.5), (86.1, 4.9), (87.0, 3.0)].iter() {
+            let weights = crate::interpolate::bilinear_weights(&field.grid(), *lat, *lon).unwrap();
+            assert!((weights.iter().map(|(_, weight)| weight).sum::<f64>() - 1.0).abs() < 1e-12);
+
+            let expected = 2.0 * lon + 3.0 * (90.0 - lat);
+            for method in [crate::interpolate::PointInterpolation::Bilinear, crate::interpolate::PointInterpolation::Bicubic].iter() {
+                assert!((field.interpolate(*lat, *lon, *method).unwrap() as f64 - expected).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn regrid_constant_and_identity() {
+        let values: Vec<f32> = (0..48).map(|point| (2 * (point % 8) + 3 * (point / 8)) as f32).collect();
+        let constant: Vec<f32> = vec![7.5; 48];
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(8, 6), &[], &[
+            (255, &[], &values),
+            (255, &[], &constant)
+        ])).unwrap();
+        let (field, constant_field) = (&message.fields()[0], &message.fields()[1]);
+
+        // two degree cells lying inside the source grid
+        let coarser = vec![6, 0, 0, 0, 0, 0, 0, 4, 3, 0, 0, 89500000, 500000, 48, 85500000, 6500000, 2000000, 2000000, 0];
+
+        for method in [crate::regrid::RegridMethod::Nearest, crate::regrid::RegridMethod::Bilinear, crate::regrid::RegridMethod::Conservative].iter() {
+            let identity = field.regrid(0, &latlon_template(8, 6), *method).unwrap();
+            assert!(identity.data().iter().zip(field.data().iter()).all(|(a, b)| (a - b).abs() < 1e-4));
+
+            let remapped = constant_field.regrid(0, &coarser, *method).unwrap();
+            assert_eq!(remapped.data().len(), 12);
+            assert!(remapped.data().iter().all(|value| (value - 7.5).abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn regrid_conservative_keeps_global_mean() {
+        // 10 degree cells onto 20 degree cells offset by half a source cell in longitude
+        let source = vec![6, 0, 0, 0, 0, 0, 0, 36, 18, 0, 0, 85000000, 5000000, 48, -85000000, 355000000, 10000000, 10000000, 0];
+        let target = vec![6, 0, 0, 0, 0, 0, 0, 18, 9, 0, 0, 80000000, 0, 48, -80000000, 340000000, 20000000, 20000000, 0];
+        let values: Vec<f32> = (0..648).map(|point| ((point % 36) * 10 + point / 36) as f32).collect();
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &source, &[], &[(255, &[], &values)])).unwrap();
+        let field = &message.fields()[0];
+        let remapped = field.regrid(0, &target, crate::regrid::RegridMethod::Conservative).unwrap();
+
+        let (before, after) = (field.area_weighted_mean().unwrap(), remapped.area_weighted_mean().unwrap());
+        assert!((before - after).abs() < 1e-3);
+    }
+
+    #[test]
+    fn subset_across_prime_meridian() {
+        let values: Vec<f32> = (0..1080).map(|point| (point % 360) as f32).collect();
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(360, 3), &[], &[
+            (255, &[], &values)
+        ])).unwrap();
+
+        let bbox = crate::subset::BoundingBox::new(90.0, 89.0, -2.0, 2.0);
+        let subset = message.fields()[0].subset(&bbox).unwrap();
+
+        let template = subset.grid_template();
+        assert_eq!((template[7], template[8]), (5, 2));
+        assert_eq!((template[11], template[12], template[14], template[15]), (90000000, 358000000, 89000000, 2000000));
+        let expected = [358.0, 359.0, 0.0, 1.0, 2.0, 358.0, 359.0, 0.0, 1.0, 2.0];
+        assert_eq!(subset.data().len(), expected.len());
+        assert!(subset.data().iter().zip(expected.iter()).all(|(value, expected)| (value - expected).abs() < 1e-3));
+    }
+
+    #[test]
+    fn longitude_conventions_round_trip() {
+        let values: Vec<f32> = (0..720).map(|point| (point % 360) as f32).collect();
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(360, 2), &[], &[
+            (255, &[], &values)
+        ])).unwrap();
+        let field = &message.fields()[0];
+
+        let signed = field.convert_longitudes(crate::longitude::LongitudeConvention::Signed).unwrap();
+        assert_eq!((signed.grid_template()[12], signed.grid_template()[15]), (180000000, 179000000));
+        assert_eq!(signed.longitude_convention(), Some(crate::longitude::LongitudeConvention::Signed));
+        assert!((signed.data()[0] - 180.0).abs() < 1e-3);
+
+        match signed.coordinates().unwrap() {
+            crate::grid::GridCoordinates::Axes { longitudes, .. } => {
+                assert_eq!((longitudes[0], longitudes[359]), (-180.0, 179.0));
+            },
+            _ => panic!("lat/lon grids have axes")
+        }
+
+        let positive = signed.convert_longitudes(crate::longitude::LongitudeConvention::Positive).unwrap();
+        assert_eq!(positive.grid_template(), field.grid_template());
+        assert_eq!(positive.data(), field.data());
+
+        // the convention is not written to the message, so the signed field reads back as Positive
+        let encoded = encode_message(0, signed.grid_template(), &[], &[(255, &[], signed.data())]);
+        let decoded = crate::Grib2Message::from_bytes(encoded).unwrap();
+        let reread = crate::Grib2Message::from_bytes(decoded.bytes().clone()).unwrap();
+        assert_eq!(reread.fields()[0].grid_template(), signed.grid_template());
+        assert_eq!(reread.fields()[0].longitude_convention(), Some(crate::longitude::LongitudeConvention::Positive));
+    }
+
+    #[test]
+    fn global_cell_areas() {
+        let sphere = 4.0 * std::f64::consts::PI * 6371229.0f64.powi(2);
+        let total = |grid: crate::grid::GridDefinition| grid.cell_areas().unwrap().iter().sum::<f64>();
+
+        let latlon = crate::grid::GridDefinition::new(0, &latlon_template(360, 181));
+        assert!((total(latlon) / sphere - 1.0).abs() < 1e-9);
+
+        let gaussian = crate::grid::GridDefinition::new(40, &vec![
+            6, 0, 0, 0, 0, 0, 0, 192, 96, 0, 0, 88572169, 0, 48, -88572169, 358125000, 1875000, 48, 0
+        ]);
+        assert!((total(gaussian) / sphere - 1.0).abs() < 1e-9);
+
+        // the surface of the WGS84 ellipsoid, and a sphere of its equatorial radius for rotated grids
+        let (a, b) = (6378137.0f64, 6356752.314245f64);
+        let e = (1.0 - (b * b) / (a * a)).sqrt();
+        let ellipsoid = 2.0 * std::f64::consts::PI * a * a * (1.0 + (1.0 - e * e) / e * e.atanh());
+
+        let mut wgs84 = latlon_template(360, 181);
+        wgs84[0] = 5;
+        assert!((total(crate::grid::GridDefinition::new(0, &wgs84)) / ellipsoid - 1.0).abs() < 1e-9);
+
+        wgs84.extend_from_slice(&[-30000000, 15000000, 0]);
+        let rotated = crate::grid::GridDefinition::new(1, &wgs84);
+        assert!((total(rotated) / (4.0 * std::f64::consts::PI * a * a) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weighted_mean_skips_missing_points() {
+        let flags: Vec<i64> = vec![1, 0, 1, 1, 0, 1];
+        let values: Vec<f32> = vec![1.0, 100.0, 3.0, 4.0, 100.0, 6.0];
+
+        let message = crate::Grib2Message::from_bytes(encode_message(0, &latlon_template(3, 2), &[], &[
+            (0, &flags, &values)
+        ])).unwrap();
+
+        let mean = message.fields()[0].weighted_mean(&[1.0; 6]).unwrap();
+        assert!((mean - 3.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn points_walk() {
+        let flags: Vec<i64> = vec![1, 0, 1, 1, 0, 1];
+        let values: Vec<f32> = vec![1.0, 0.0, 3.0, 4.0, 0.0, 6.0];
+
+        // rows alternating in direction, the second running east to west
+        let mut boustrophedon = latlon_template(3, 2);
+        boustrophedon[18] = 0x10;
+
+        let masked = crate::Grib2Message::from_bytes(encode_message(0, &boustrophedon, &[], &[(0, &flags, &values)])).unwrap();
+        let points: Vec<(usize, f64, f64, Option<f32>)> = masked.fields()[0].points().collect();
+
+        assert_eq!(points.len(), 6);
+        assert_eq!(points.iter().map(|point| point.3.is_some()).collect::<Vec<bool>>(), vec![true, false, true, true, false, true]);
+        assert_eq!(points.iter().map(|point| (point.1, point.2)).collect::<Vec<(f64, f64)>>(), vec![
+            (90.0, 0.0), (90.0, 1.0), (90.0, 2.0), (89.0, 2.0), (89.0, 1.0), (89.0, 0.0)
+        ]);
+
+        // a global reduced Gaussian grid with two parallels between pole and equator
+        let latitudes = crate::grid::gaussian_latitudes(2);
+        let first = (latitudes[0] * 1e6).round() as i64;
+        let counts: Vec<i64> = vec![4, 8, 8, 4];
+        let reduced = vec![6, 0, 0, 0, 0, 0, 0, 4294967295, 4, 0, 0, first, 0, 48, -first, 315000000, 4294967295, 2, 0];
+        let values: Vec<f32> = (0..24).map(|point| point as f32).collect();
+
+        let message = crate::Grib2Message::from_bytes(encode_message(40, &reduced, &counts, &[(255, &[], &values)])).unwrap();
+        let points: Vec<(usize, f64, f64, Option<f32>)> = message.fields()[0].points().collect();
+
+        assert_eq!(points.len(), 24);
+        for (index, row, lon) in [(3, 0, 270.0), (4, 1, 0.0), (5, 1, 45.0), (19, 2, 315.0), (20, 3, 0.0), (23, 3, 270.0)].iter() {
+            let (_, lat, point_lon, value) = points[*index];
+            assert!((lat - latitudes[*row]).abs() < 1e-6 && (point_lon - lon).abs() < 1e-6);
+            assert!((value.unwrap() - *index as f32).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn grid_relative_winds() {
+        // 30 km grids with grid-relative winds: Lambert west of LoV, and polar stereographic over the south pole
+        let lambert = vec![6, 0, 0, 0, 0, 0, 0, 5, 5, 40000000, 240000000, 8, 38500000, 262500000, 30000000, 30000000, 0, 64, 38500000, 38500000, -90000000, 0];
+        let south_polar = vec![6, 0, 0, 0, 0, 0, 0, 5, 5, -60000000, 30000000, 8, -60000000, 100000000, 30000000, 30000000, 0x80, 64];
+
+        // a wind of 10 m/s along the grid's x axis everywhere
+        let u: Vec<f32> = vec![10.0; 25];
+        let v: Vec<f32> = vec![0.0; 25];
+
+        for (number, template) in [(30, lambert), (20, south_polar)].iter() {
+            let message = crate::Grib2Message::from_bytes(encode_message(*number, template, &[], &[(255, &[], &u), (255, &[], &v)])).unwrap();
+            let (eastward, northward) = crate::wind::earth_relative_winds(&message.fields()[0], &message.fields()[1]).unwrap();
+            let grid = message.fields()[0].grid();
+
+            // the direction of the x axis on the earth at the centre point, from its neighbours along i
+            let (west, east) = (grid.latlon(1.5, 2.0).unwrap(), grid.latlon(2.5, 2.0).unwrap());
+            let dx = (east.1 - west.1) * ((east.0 + west.0) / 2.0).to_radians().cos();
+            let dy = east.0 - west.0;
+            let length = dx.hypot(dy);
+
+            let (u_earth, v_earth) = (eastward.data()[12] as f64, northward.data()[12] as f64);
+            assert!((u_earth - 10.0 * dx / length).abs() < 1e-2 && (v_earth - 10.0 * dy / length).abs() < 1e-2);
+            assert!(!eastward.grid().resolution_flags().unwrap().grid_relative_vectors);
+
+            // west of LoV the meridians lean towards +x as they converge on the north pole
+            if *number == 30 {
+                assert!(v_earth > 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn expand_reduced_rows() {
+        use crate::reduced::RowInterpolation;
+
+        // global rows of 4, 8 and 4 points 45 degrees apart in latitude, and the same on a Gaussian grid
+        let missing = 4294967295;
+        let latlon = vec![6, 0, 0, 0, 0, 0, 0, missing, 3, 0, 0, 45000000, 0, 16, -45000000, 315000000, missing, 45000000, 0];
+        let first = (crate::grid::gaussian_latitudes(2)[0] * 1e6).round() as i64;
+        let gaussian = vec![6, 0, 0, 0, 0, 0, 0, missing, 4, 0, 0, first, 0, 16, -first, 315000000, missing, 2, 0];
+
+        let short_row: Vec<f32> = vec![0.0, 10.0, 20.0, 30.0];
+        let long_row: Vec<f32> = (0..8).map(|point| point as f32).collect();
+
+        // the last point of a short row lies between its last and first points
+        let linear: Vec<f32> = vec![0.0, 5.0, 10.0, 15.0, 20.0, 25.0, 30.0, 15.0];
+        let nearest: Vec<f32> = vec![0.0, 10.0, 10.0, 20.0, 20.0, 30.0, 30.0, 0.0];
+
+        let cases: [(i64, &Vec<i64>, Vec<i64>); 2] = [(0, &latlon, vec![4, 8, 4]), (40, &gaussian, vec![4, 8, 8, 4])];
+
+        for (number, template, counts) in cases.iter() {
+            let values: Vec<f32> = counts
+                .iter()
+                .flat_map(|count| if *count == 4 { short_row.clone() } else { long_row.clone() })
+                .collect();
+
+            let message = crate::Grib2Message::from_bytes(encode_message(*number, template, counts, &[(255, &[], &values)])).unwrap();
+            let field = &message.fields()[0];
+
+            for (method, expected_short) in [(RowInterpolation::Linear, &linear), (RowInterpolation::Nearest, &nearest)].iter() {
+                let expanded = field.expand_reduced(*method).unwrap();
+                let template = expanded.grid_template();
+
+                assert!(expanded.get_grid_arrays_list().cloned().unwrap_or_default().is_empty());
+                assert_eq!(template[7], 8);
+                assert_ne!(template[13] & 0x20, 0);
+                assert_eq!((template[15], template[16]), (315000000, 45000000));
+                assert_eq!(expanded.grid().grid_shape(), Some((8, counts.len())));
+
+                let expected: Vec<f32> = counts
+                    .iter()
+                    .flat_map(|count| if *count == 4 { (*expected_short).clone() } else { long_row.clone() })
+                    .collect();
+                assert_eq!(expanded.data().len(), expected.len());
+                assert!(expanded.data().iter().zip(expected.iter()).all(|(value, expected)| (value - expected).abs() < 1e-3));
+            }
+        }
+    }
+
     #[test]
     fn grib2_open() {
         let mut g2 = crate::Grib2::new(String::from("test.grib2"));
@@ -0,0 +1,97 @@
+use crate::{Grib2Error, Grib2Field, grid::GridDefinition};
+
+/*
+Conversion of global grids between the two longitude conventions in use.
+
+The columns of the grid are rolled so that the rows start at the western (or, scanning
+towards the west, eastern) end of the new range, and the first and last longitudes of the
+grid definition are rewritten to match. Longitudes in the template are unsigned whatever
+the convention, so the convention itself is kept with the field and applied to the
+longitudes it gives out.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongitudeConvention {
+    // 0 to 360 degrees east, as used by GFS
+    Positive,
+    // -180 to 180 degrees, centred on the Greenwich meridian
+    Signed
+}
+
+impl LongitudeConvention {
+    // the longitude in degrees moved into this convention's range
+    pub fn normalise(&self, lon: f64) -> f64 {
+        match self {
+            LongitudeConvention::Positive => lon.rem_euclid(360.0),
+            LongitudeConvention::Signed => (lon + 180.0).rem_euclid(360.0) - 180.0
+        }
+    }
+}
+
+// the longitudes of the columns of a regular lat/lon or Gaussian grid, with the template's angle unit
+fn columns(grid: &GridDefinition) -> Option<(Vec<f64>, f64)> {
+    match grid {
+        GridDefinition::LatLon(def) if !def.is_reduced() => Some((def.longitudes(), def.angle_unit())),
+        GridDefinition::Gaussian(def) if !def.is_reduced() => Some((def.longitudes(), def.angle_unit())),
+        _ => None
+    }
+}
+
+impl Grib2Field {
+    /*
+    The convention of a lat/lon or Gaussian grid: the one set by convert_longitudes(), or else
+    Positive. Templates always hold longitudes between 0 and 360 degrees, so the convention is
+    not kept in the message, and a field read back from its encoded bytes is Positive again.
+    */
+    pub fn longitude_convention(&self) -> Option<LongitudeConvention> {
+        columns(&self.grid())?;
+        Some(self.longitude_convention.unwrap_or(LongitudeConvention::Positive))
+    }
+
+    /*
+    Rolls a field on a global regular lat/lon (3.0) or Gaussian (3.40) grid into the given
+    longitude convention. The points keep the field's scanning mode, and the longitudes are
+    written between 0 and 360 degrees in the units of the template's basic angle. Grids which
+    do not go all the way around the globe cannot be rolled.
+    */
+    pub fn convert_longitudes(&self, convention: LongitudeConvention) -> Result<Grib2Field, Grib2Error> {
+        let grid = self.grid();
+        let (longitudes, unit) = columns(&grid).ok_or(Grib2Error::FunctionNotImplemented)?;
+        let (_, nj) = grid.grid_shape().ok_or(Grib2Error::FunctionNotImplemented)?;
+
+        if !grid.wraps_longitude() || longitudes.len() < 2 {
+            return Err(Grib2Error::FunctionNotImplemented);
+        }
+
+        let normalised: Vec<f64> = longitudes.iter().map(|lon| convention.normalise(*lon)).collect();
+        let eastward = longitudes[1] > longitudes[0];
+
+        // rows start at the smallest longitude going east, or at the largest going west
+        let start = (0..normalised.len())
+            .min_by(|a, b| {
+                let order = normalised[*a].total_cmp(&normalised[*b]);
+                if eastward { order } else { order.reverse() }
+            })
+            .unwrap_or(0);
+
+        let rolled: Vec<usize> = (0..longitudes.len()).map(|column| (start + column) % longitudes.len()).collect();
+        let rows: Vec<usize> = (0..nj).collect();
+
+        let mut field = self.crop(&grid, &rolled, &rows)?;
+
+        // the first and last longitudes are at the same place in 3.0 and 3.40, and are unsigned
+        field.grid_template[12] = (longitudes[rolled[0]].rem_euclid(360.0) / unit).round() as i64;
+        field.grid_template[15] = (longitudes[rolled[rolled.len() - 1]].rem_euclid(360.0) / unit).round() as i64;
+        field.longitude_convention = Some(convention);
+
+        Ok(field)
+    }
+
+    // a longitude of a point of the field in degrees, in the convention set by convert_longitudes()
+    pub(crate) fn presented_longitude(&self, lon: f64) -> f64 {
+        match self.longitude_convention {
+            Some(convention) => convention.normalise(lon),
+            None => lon
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{Grib2Field, earth::EarthModel, grid::GridDefinition};
+
+/*
+Nearest grid point lookup.
+
+Candidate points are found from the fractional grid index of the target (by the inverse
+projection, or the Gaussian row search), and the closest of the surrounding points on the
+earth is picked. Unstructured grids are searched point by point.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestPoint {
+    // position of the point in data()
+    pub index: usize,
+    pub latitude: f64,
+    pub longitude: f64,
+    // great circle distance from the requested position, in metres
+    pub distance: f64,
+    // None where the bitmap marks the point as missing
+    pub value: Option<f32>
+}
+
+// the fractional index along the given row of a reduced grid for a longitude
+fn row_position(grid: &GridDefinition, row: usize, lon: f64) -> Option<f64> {
+    let (_, first) = grid.latlon(0.0, row as f64)?;
+    let (_, second) = grid.latlon(1.0, row as f64)?;
+    let increment = second - first;
+
+    if increment == 0.0 {
+        Some(0.0)
+    } else {
+        Some((increment.signum() * (lon - first)).rem_euclid(360.0) / increment.abs())
+    }
+}
+
+/*
+The points of the grid surrounding the given latitude and longitude, as (row, point in row).
+Targets more than half a grid length outside the grid have no candidates, except past the
+first or last row of grids wrapping around the globe, which reach the poles.
+*/
+pub(crate) fn surrounding_points(grid: &GridDefinition, lat: f64, lon: f64) -> Vec<(usize, usize)> {
+    let (counts, (i, j)) = match (grid.row_point_counts(), grid.grid_index(lat, lon)) {
+        (Some(counts), Some(index)) => (counts, index),
+        _ => return Vec::new()
+    };
+
+    let wraps = grid.wraps_longitude();
+    let reduced = grid.grid_shape().is_none();
+    let rows = counts.len() as f64;
+
+    if counts.is_empty() || !i.is_finite() || !j.is_finite() || (!wraps && (j < -0.5 || j > rows - 0.5)) {
+        return Vec::new();
+    }
+
+    let first_row = j.floor().max(0.0).min(rows - 1.0) as usize;
+    let last_row = j.ceil().max(0.0).min(rows - 1.0) as usize;
+
+    let mut points = Vec::new();
+
+    for (row, count) in counts.iter().copied().enumerate().take(last_row + 1).skip(first_row) {
+
+        // rows of reduced grids are spaced differently, so each gets its own index
+        let i = if reduced { row_position(grid, row, lon).unwrap_or(i) } else { i };
+
+        if !wraps && (i < -0.5 || i > count as f64 - 0.5) {
+            continue;
+        }
+
+        for point in [i.floor() as i64, i.floor() as i64 + 1].iter() {
+            let point = if wraps { point.rem_euclid(count as i64) } else { *point };
+
+            if point >= 0 && (point as usize) < count && !points.contains(&(row, point as usize)) {
+                points.push((row, point as usize));
+            }
+        }
+    }
+
+    points
+}
+
+// position in data() of point `point` of row `row`
+pub(crate) fn point_storage_index(grid: &GridDefinition, counts: &[usize], row: usize, point: usize) -> usize {
+    match (grid.grid_shape(), grid.scanning_mode()) {
+        (Some((ni, nj)), Some(mode)) => mode.storage_index(point, row, ni, nj),
+        _ => counts[..row].iter().sum::<usize>() + point
+    }
+}
+
+/*
+The grid point nearest to a position, as its position in data(), its own latitude and longitude,
+and its distance from the position in metres.
+*/
+pub(crate) fn nearest_grid_point(grid: &GridDefinition, earth: &EarthModel, lat: f64, lon: f64) -> Option<(usize, f64, f64, f64)> {
+    let counts = grid.row_point_counts()?;
+
+    surrounding_points(grid, lat, lon)
+        .into_iter()
+        .filter_map(|(row, point)| {
+            let (point_lat, point_lon) = grid.latlon(point as f64, row as f64)?;
+            Some((
+                point_storage_index(grid, &counts, row, point),
+                point_lat,
+                point_lon,
+                earth.distance(lat, lon, point_lat, point_lon)
+            ))
+        })
+        .filter(|point| point.3.is_finite())
+        .min_by(|a, b| a.3.total_cmp(&b.3))
+}
+
+impl Grib2Field {
+    /*
+    The value of the grid point nearest to the given latitude and longitude in degrees, along
+    with the point's position in data() and its own coordinates. None when the position is
+    outside the grid, or the grid cannot be located (unsupported templates, or unstructured
+    grids without attached coordinates).
+    */
+    pub fn value_at(&self, lat: f64, lon: f64) -> Option<NearestPoint> {
+        let grid = self.grid();
+        let earth = grid.earth_model().unwrap_or(EarthModel::Sphere(6371229.0));
+        let values = self.data();
+
+        let nearest = |index: usize, point_lat: f64, point_lon: f64, distance: f64| NearestPoint {
+            index,
+            latitude: point_lat,
+            longitude: self.presented_longitude(point_lon),
+            distance,
+            value: if self.is_present(index) { values.get(index).copied() } else { None }
+        };
+
+        if let (GridDefinition::Unstructured(_), Some(coordinates)) = (&grid, self.unstructured_coordinates()) {
+            return coordinates.latitudes()
+                .iter()
+                .zip(coordinates.longitudes().iter())
+                .enumerate()
+                .map(|(index, (point_lat, point_lon))| nearest(index, *point_lat, *point_lon, earth.distance(lat, lon, *point_lat, *point_lon)))
+                .filter(|point| point.distance.is_finite())
+                .min_by(|a, b| a.distance.total_cmp(&b.distance));
+        }
+
+        nearest_grid_point(&grid, &earth, lat, lon)
+            .filter(|point| point.0 < values.len())
+            .map(|(index, point_lat, point_lon, distance)| nearest(index, point_lat, point_lon, distance))
+    }
+}
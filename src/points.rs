@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use crate::{Grib2Field, grid::{GridDefinition, ScanningMode, UnstructuredGridCoordinates}};
+
+/*
+Walking the points of a field along with their coordinates.
+
+Only what is needed to locate a single point is kept: the axes of regular lat/lon and
+Gaussian grids, one entry per row of reduced grids, and the grid definition itself for
+projected grids, whose points are located one at a time.
+*/
+
+// how the coordinates of the point at a position in data() are found
+enum Layout {
+    // latitudes along j and longitudes along i, in the scanning directions
+    Axes {
+        mode: ScanningMode,
+        latitudes: Vec<f64>,
+        longitudes: Vec<f64>
+    },
+    // reduced grids, (latitude, first longitude, longitude increment, point count) of each row
+    Rows(Vec<(f64, f64, f64, usize)>),
+    Projected {
+        mode: ScanningMode,
+        shape: (usize, usize)
+    },
+    Unstructured(Arc<UnstructuredGridCoordinates>),
+    // unstructured grids without coordinates and unsupported templates
+    Unknown
+}
+
+/*
+Iterator over the points of a field in the order they are stored, giving the position of each
+point in data(), its latitude and longitude in degrees, and its value (None where the bitmap
+marks the point as missing). Points which cannot be located, such as those off the disk of
+a space view, or every point of an unsupported grid, have NaN coordinates.
+*/
+pub struct Points<'a> {
+    field: &'a Grib2Field,
+    grid: GridDefinition,
+    layout: Layout,
+    index: usize,
+    // position along the rows of reduced grids, as (row, point in row)
+    row_position: (usize, usize)
+}
+
+impl<'a> Points<'a> {
+    fn new(field: &'a Grib2Field) -> Points<'a> {
+        let grid = field.grid();
+
+        let layout = match (&grid, grid.grid_shape(), grid.scanning_mode()) {
+            (GridDefinition::LatLon(def), Some(_), Some(mode)) => Layout::Axes {
+                mode,
+                latitudes: def.latitudes(),
+                longitudes: def.longitudes()
+            },
+            (GridDefinition::Gaussian(def), Some(_), Some(mode)) => Layout::Axes {
+                mode,
+                latitudes: def.latitudes(),
+                longitudes: def.longitudes()
+            },
+            (GridDefinition::LatLon(def), None, _) => Layout::Rows(
+                def.latitudes()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, lat)| (lat, def.first_gridpoint().1, def.row_increment(row), def.row_point_count(row)))
+                    .collect()
+            ),
+            (GridDefinition::Gaussian(def), None, _) => Layout::Rows(
+                def.latitudes()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, lat)| (lat, def.first_gridpoint().1, def.row_increment(row), def.row_point_count(row)))
+                    .collect()
+            ),
+            (GridDefinition::Unstructured(_), _, _) => match field.unstructured_coordinates() {
+                Some(coordinates) => Layout::Unstructured(coordinates.clone()),
+                None => Layout::Unknown
+            },
+            (_, Some(shape), Some(mode)) => Layout::Projected {
+                mode,
+                shape
+            },
+            _ => Layout::Unknown
+        };
+
+        Points {
+            field,
+            grid,
+            layout,
+            index: 0,
+            row_position: (0, 0)
+        }
+    }
+
+    // latitude and longitude of the point at the current position
+    fn locate(&mut self) -> Option<(f64, f64)> {
+        match &self.layout {
+            Layout::Axes { mode, latitudes, longitudes } => {
+                let (i, j) = mode.grid_index(self.index, longitudes.len(), latitudes.len());
+                Some((*latitudes.get(j)?, *longitudes.get(i)?))
+            },
+            Layout::Rows(rows) => {
+                // skip rows which have no points
+                let (mut row, mut point) = self.row_position;
+                while rows.get(row).is_some_and(|entry| point >= entry.3) {
+                    row += 1;
+                    point = 0;
+                }
+
+                self.row_position = (row, point + 1);
+
+                let (lat, first_lon, increment, _) = rows.get(row)?;
+                Some((*lat, first_lon + point as f64 * increment))
+            },
+            Layout::Projected { mode, shape } => {
+                let (i, j) = mode.grid_index(self.index, shape.0, shape.1);
+                self.grid.latlon(i as f64, j as f64)
+            },
+            Layout::Unstructured(coordinates) => coordinates.latlon(self.index),
+            Layout::Unknown => None
+        }
+    }
+}
+
+impl<'a> Iterator for Points<'a> {
+    type Item = (usize, f64, f64, Option<f32>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        let value = *self.field.data().get(index)?;
+
+        let (lat, lon) = self.locate().unwrap_or((f64::NAN, f64::NAN));
+        self.index += 1;
+
+        Some((index, lat, self.field.presented_longitude(lon), if self.field.is_present(index) { Some(value) } else { None }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.field.data().len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Points<'a> {}
+
+impl Grib2Field {
+    // every point of the field with its coordinates and value, see Points
+    pub fn points(&self) -> Points<'_> {
+        Points::new(self)
+    }
+}
@@ -76,6 +76,28 @@ impl HorizontalLayerProductDefinition {
         })
     }
 
+    // the inverse of new(), returns the first 15 values of the product definition template
+    // scale factors are signed in the template, so they are converted back through i8
+    pub fn template_values(&self) -> Vec<i64> {
+        vec![
+            self.parameter_category as i64,
+            self.parameter_number as i64,
+            self.generating_process as i64,
+            self.background_generating_process_identifier as i64,
+            self.generating_process_identified as i64,
+            self.hours_after_ref_time as i64,
+            self.minutes_after_ref_time as i64,
+            self.indicator_of_time_range_unit as i64,
+            self.forecast_time as i64,
+            self.first_fixed_surface_type as i64,
+            self.first_fixed_surface_scale_factor as i8 as i64,
+            self.first_fixed_surface_scale_value,
+            self.second_fixed_surface_type as i64,
+            self.second_fixed_surface_scale_factor as i8 as i64,
+            self.second_fixed_surface_scale_value
+        ]
+    }
+
     pub fn parameter_info(&self, code_table_4_2: &Codetable, prod_dsc: i64) -> Option<(String, String)> {
         code_table_4_2.parameter_number_codepoint_lookup(
             prod_dsc, 
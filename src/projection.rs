@@ -0,0 +1,343 @@
+/*
+Map projection math used by the grid definitions.
+
+Everything here works on an ellipsoid given by its semi-major axis `a` (metres) and
+eccentricity `e`; a sphere is the case e = 0. Angles are taken and returned in degrees,
+plane coordinates are in metres. Formulas follow Snyder, "Map Projections: A Working Manual".
+*/
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+// wraps an angle in radians to [-pi, pi)
+pub(crate) fn wrap_radians(angle: f64) -> f64 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+// Snyder (14-15)
+fn m(phi: f64, e: f64) -> f64 {
+    phi.cos() / (1.0 - (e * phi.sin()).powi(2)).sqrt()
+}
+
+// Snyder (15-9)
+fn t(phi: f64, e: f64) -> f64 {
+    let es = e * phi.sin();
+    (FRAC_PI_4 - phi / 2.0).tan() / ((1.0 - es) / (1.0 + es)).powf(e / 2.0)
+}
+
+// inverts t() for the latitude, Snyder (7-9)
+fn phi_from_t(t: f64, e: f64) -> f64 {
+    let mut phi = FRAC_PI_2 - 2.0 * t.atan();
+
+    for _ in 0..15 {
+        let es = e * phi.sin();
+        let next = FRAC_PI_2 - 2.0 * (t * ((1.0 - es) / (1.0 + es)).powf(e / 2.0)).atan();
+
+        if (next - phi).abs() < 1e-12 {
+            return next;
+        }
+
+        phi = next;
+    }
+
+    phi
+}
+
+/*
+Lambert conformal conic with one or two standard parallels.
+The origin of the plane is the apex of the cone, so only differences between points are meaningful.
+*/
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LambertConic {
+    a: f64,
+    e: f64,
+    n: f64,
+    f: f64,
+    lon0: f64
+}
+
+impl LambertConic {
+    pub(crate) fn new(a: f64, e: f64, latin1: f64, latin2: f64, lon0: f64) -> LambertConic {
+        let (phi1, phi2) = (latin1.to_radians(), latin2.to_radians());
+        let (m1, m2) = (m(phi1, e), m(phi2, e));
+        let (t1, t2) = (t(phi1, e), t(phi2, e));
+
+        let n = if (phi1 - phi2).abs() < 1e-10 {
+            phi1.sin()
+        } else {
+            (m1.ln() - m2.ln()) / (t1.ln() - t2.ln())
+        };
+
+        LambertConic {
+            a,
+            e,
+            n,
+            f: m1 / (n * t1.powf(n)),
+            lon0: lon0.to_radians()
+        }
+    }
+
+    pub(crate) fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let rho = self.a * self.f * t(lat.to_radians(), self.e).powf(self.n);
+        let theta = self.n * wrap_radians(lon.to_radians() - self.lon0);
+
+        (rho * theta.sin(), -rho * theta.cos())
+    }
+
+    pub(crate) fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let rho = self.n.signum() * (x * x + y * y).sqrt();
+
+        let theta = if self.n > 0.0 { x.atan2(-y) } else { (-x).atan2(y) };
+        let lon = wrap_radians(theta / self.n + self.lon0);
+
+        let lat = if rho == 0.0 {
+            self.n.signum() * FRAC_PI_2
+        } else {
+            phi_from_t((rho / (self.a * self.f)).powf(1.0 / self.n), self.e)
+        };
+
+        (lat.to_degrees(), lon.to_degrees())
+    }
+
+    // the angle in degrees that the plane's axes are turned clockwise from east and north at a longitude
+    pub(crate) fn convergence(&self, lon: f64) -> f64 {
+        (self.n * wrap_radians(lon.to_radians() - self.lon0)).to_degrees()
+    }
+
+    // ratio of distances on the plane to distances on the ellipsoid at the given latitude
+    pub(crate) fn scale(&self, lat: f64) -> f64 {
+        let phi = lat.to_radians();
+        self.n * self.f * t(phi, self.e).powf(self.n) / m(phi, self.e)
+    }
+}
+
+/*
+Polar stereographic, true to scale at the given latitude.
+Southern projections are handled by mirroring the northern formulas, Snyder (21-33) onwards.
+*/
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PolarStereographic {
+    a: f64,
+    e: f64,
+    south: bool,
+    rho_factor: f64,
+    lon0: f64
+}
+
+impl PolarStereographic {
+    pub(crate) fn new(a: f64, e: f64, south: bool, true_scale_lat: f64, lon0: f64) -> PolarStereographic {
+        let phi_c = if south { -true_scale_lat } else { true_scale_lat }.to_radians();
+
+        let rho_factor = if (FRAC_PI_2 - phi_c).abs() < 1e-10 {
+            2.0 / ((1.0 + e).powf(1.0 + e) * (1.0 - e).powf(1.0 - e)).sqrt()
+        } else {
+            m(phi_c, e) / t(phi_c, e)
+        };
+
+        PolarStereographic {
+            a,
+            e,
+            south,
+            rho_factor,
+            lon0: lon0.to_radians()
+        }
+    }
+
+    // latitude as seen from the northern form of the projection
+    fn hemisphere_phi(&self, lat: f64) -> f64 {
+        if self.south { -lat.to_radians() } else { lat.to_radians() }
+    }
+
+    pub(crate) fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let rho = self.a * self.rho_factor * t(self.hemisphere_phi(lat), self.e);
+        let dlon = wrap_radians(lon.to_radians() - self.lon0);
+
+        if self.south {
+            (rho * dlon.sin(), rho * dlon.cos())
+        } else {
+            (rho * dlon.sin(), -rho * dlon.cos())
+        }
+    }
+
+    // the angle in degrees that the plane's axes are turned clockwise from east and north at a longitude
+    pub(crate) fn convergence(&self, lon: f64) -> f64 {
+        let dlon = wrap_radians(lon.to_radians() - self.lon0).to_degrees();
+        if self.south { -dlon } else { dlon }
+    }
+
+    pub(crate) fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let rho = (x * x + y * y).sqrt();
+        let phi = phi_from_t(rho / (self.a * self.rho_factor), self.e);
+
+        let (lat, dlon) = if self.south {
+            (-phi, x.atan2(y))
+        } else {
+            (phi, x.atan2(-y))
+        };
+
+        (lat.to_degrees(), wrap_radians(dlon + self.lon0).to_degrees())
+    }
+}
+
+// Mercator, true to scale at the given latitude, Snyder (7-1) and (7-2) for the ellipsoid
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Mercator {
+    a: f64,
+    e: f64,
+    k0: f64,
+    lon0: f64
+}
+
+impl Mercator {
+    pub(crate) fn new(a: f64, e: f64, true_scale_lat: f64, lon0: f64) -> Mercator {
+        Mercator {
+            a,
+            e,
+            k0: m(true_scale_lat.to_radians(), e),
+            lon0: lon0.to_radians()
+        }
+    }
+
+    pub(crate) fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        (
+            self.a * self.k0 * wrap_radians(lon.to_radians() - self.lon0),
+            -self.a * self.k0 * t(lat.to_radians(), self.e).ln()
+        )
+    }
+
+    pub(crate) fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let phi = phi_from_t((-y / (self.a * self.k0)).exp(), self.e);
+        let lon = wrap_radians(x / (self.a * self.k0) + self.lon0);
+
+        (phi.to_degrees(), lon.to_degrees())
+    }
+}
+
+/*
+Rotation of the sphere moving the south pole to the given point, for rotated lat/lon grids.
+The angle of rotation turns the grid about the rotated polar axis, increasing rotated longitudes.
+*/
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PoleRotation {
+    matrix: [[f64; 3]; 3],
+    angle: f64
+}
+
+fn to_cartesian(lat: f64, lon: f64) -> [f64; 3] {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn from_cartesian(v: [f64; 3]) -> (f64, f64) {
+    (v[2].clamp(-1.0, 1.0).asin().to_degrees(), v[1].atan2(v[0]).to_degrees())
+}
+
+impl PoleRotation {
+    pub(crate) fn new(south_pole_lat: f64, south_pole_lon: f64, angle: f64) -> PoleRotation {
+        let (sin_t, cos_t) = (-(90.0 + south_pole_lat)).to_radians().sin_cos();
+        let (sin_o, cos_o) = (-south_pole_lon).to_radians().sin_cos();
+
+        PoleRotation {
+            matrix: [
+                [cos_t * cos_o, sin_o, sin_t * cos_o],
+                [-cos_t * sin_o, cos_o, -sin_t * sin_o],
+                [-sin_t, 0.0, cos_t]
+            ],
+            angle
+        }
+    }
+
+    // rotated latitude and longitude to geographic, in degrees
+    pub(crate) fn to_geographic(self, lat: f64, lon: f64) -> (f64, f64) {
+        let v = to_cartesian(lat, lon + self.angle);
+        let m = &self.matrix;
+
+        from_cartesian([
+            m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+            m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+            m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2]
+        ])
+    }
+
+    // geographic latitude and longitude to rotated, in degrees
+    pub(crate) fn to_rotated(self, lat: f64, lon: f64) -> (f64, f64) {
+        let v = to_cartesian(lat, lon);
+        let m = &self.matrix;
+
+        let (rlat, rlon) = from_cartesian([
+            m[0][0] * v[0] + m[1][0] * v[1] + m[2][0] * v[2],
+            m[0][1] * v[0] + m[1][1] * v[1] + m[2][1] * v[2],
+            m[0][2] * v[0] + m[1][2] * v[1] + m[2][2] * v[2]
+        ]);
+
+        (rlat, wrap_radians((rlon - self.angle).to_radians()).to_degrees())
+    }
+}
+
+/*
+The view from a geostationary satellite, in scan angles (radians) east and north of the
+sub-satellite point. `h` is the distance of the satellite from the centre of the earth in metres.
+Follows the normalized geostationary projection of the CGMS LRIT/HRIT global specification.
+*/
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Geostationary {
+    a: f64,
+    b: f64,
+    h: f64,
+    lon0: f64
+}
+
+impl Geostationary {
+    pub(crate) fn new(a: f64, e: f64, h: f64, lon0: f64) -> Geostationary {
+        Geostationary {
+            a,
+            b: a * (1.0 - e * e).sqrt(),
+            h,
+            lon0: lon0.to_radians()
+        }
+    }
+
+    // None for points on the far side of the earth
+    pub(crate) fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (a2, b2) = (self.a * self.a, self.b * self.b);
+        let dlon = wrap_radians(lon.to_radians() - self.lon0);
+
+        let c_lat = (b2 / a2 * lat.to_radians().tan()).atan();
+        let e2 = 1.0 - b2 / a2;
+        let rl = self.b / (1.0 - e2 * c_lat.cos().powi(2)).sqrt();
+
+        let r1 = self.h - rl * c_lat.cos() * dlon.cos();
+        let r2 = -rl * c_lat.cos() * dlon.sin();
+        let r3 = rl * c_lat.sin();
+
+        // the line of sight has to meet the surface from the outside
+        let (px, py, pz) = (self.h - r1, -r2, r3);
+        if r1 * px / a2 + r2 * py / a2 - r3 * pz / b2 <= 0.0 {
+            return None;
+        }
+
+        let rn = (r1 * r1 + r2 * r2 + r3 * r3).sqrt();
+        Some(((-r2 / r1).atan(), (r3 / rn).asin()))
+    }
+
+    // None when the line of sight misses the earth
+    pub(crate) fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let ratio = (self.a * self.a) / (self.b * self.b);
+        let (cos_x, cos_y) = (x.cos(), y.cos());
+        let denominator = cos_y * cos_y + ratio * y.sin().powi(2);
+
+        let sd = (self.h * cos_x * cos_y).powi(2) - denominator * (self.h * self.h - self.a * self.a);
+        if sd < 0.0 {
+            return None;
+        }
+
+        let sn = (self.h * cos_x * cos_y - sd.sqrt()) / denominator;
+        let s1 = self.h - sn * cos_x * cos_y;
+        let s2 = sn * x.sin() * cos_y;
+        let s3 = sn * y.sin();
+
+        let lat = (ratio * s3 / (s1 * s1 + s2 * s2).sqrt()).atan();
+        let lon = wrap_radians((s2 / s1).atan() + self.lon0);
+
+        Some((lat.to_degrees(), lon.to_degrees()))
+    }
+}
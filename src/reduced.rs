@@ -0,0 +1,146 @@
+use crate::{Grib2Error, Grib2Field, grid::GridDefinition};
+
+/*
+Expansion of quasi-regular (reduced) grids to regular grids.
+
+Reduced lat/lon and Gaussian grids store a different number of points in each row. Every row
+is resampled to the point count of the widest row, so the result keeps the same rows and only
+gains points along them.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowInterpolation {
+    Linear,
+    Nearest
+}
+
+// the parts of a reduced grid definition the expansion needs
+struct ReducedRows {
+    counts: Vec<usize>,
+    increments: Vec<f64>,
+    angle_unit: f64,
+    first_longitude: i64,
+    scanning_mode: u8
+}
+
+impl ReducedRows {
+    fn new(grid: &GridDefinition) -> Option<ReducedRows> {
+        match grid {
+            GridDefinition::LatLon(def) if def.is_reduced() => Some(ReducedRows {
+                counts: (0..def.meridional_point_count as usize).map(|row| def.row_point_count(row)).collect(),
+                increments: (0..def.meridional_point_count as usize).map(|row| def.row_increment(row)).collect(),
+                angle_unit: def.angle_unit(),
+                first_longitude: def.first_gridpoint_longitude,
+                scanning_mode: def.scanning_mode
+            }),
+            GridDefinition::Gaussian(def) if def.is_reduced() => Some(ReducedRows {
+                counts: (0..def.row_count()).map(|row| def.row_point_count(row)).collect(),
+                increments: (0..def.row_count()).map(|row| def.row_increment(row)).collect(),
+                angle_unit: def.angle_unit(),
+                first_longitude: def.first_gridpoint_longitude,
+                scanning_mode: def.scanning_mode
+            }),
+            _ => None
+        }
+    }
+
+    fn widest(&self) -> usize {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    // whether the rows go all the way around the globe, judged by the widest row
+    fn is_global(&self) -> bool {
+        self.counts
+            .iter()
+            .zip(self.increments.iter())
+            .find(|(count, _)| **count == self.widest())
+            .is_some_and(|(count, increment)| (*count as f64 * increment.abs() - 360.0).abs() < 1e-3)
+    }
+}
+
+/*
+The value at fractional position `position` along a row, counted in points of that row.
+Global rows wrap around, regional rows are clamped to their end points.
+Linear interpolation falls back to the nearer point when the other one is missing.
+*/
+fn sample_row(row: &[Option<f32>], position: f64, global: bool, method: RowInterpolation) -> Option<f32> {
+    let count = row.len();
+    if count == 0 {
+        return None;
+    }
+
+    let index = |point: i64| -> usize {
+        if global {
+            point.rem_euclid(count as i64) as usize
+        } else {
+            point.max(0).min(count as i64 - 1) as usize
+        }
+    };
+
+    let lower = position.floor();
+    let fraction = position - lower;
+    let (a, b) = (row[index(lower as i64)], row[index(lower as i64 + 1)]);
+    let nearest = if fraction < 0.5 { a } else { b };
+
+    match (method, a, b) {
+        (RowInterpolation::Linear, Some(a), Some(b)) => Some(a + (b - a) * fraction as f32),
+        _ => nearest
+    }
+}
+
+impl Grib2Field {
+    /*
+    Expands a field on a reduced lat/lon (3.0) or reduced Gaussian (3.40) grid to the regular grid
+    with as many points in every row as the widest row has, interpolating along each row.
+    Points are laid out in the field's own scanning order. Fields on regular grids are returned
+    unchanged, other grid templates are not supported.
+    */
+    pub fn expand_reduced(&self, method: RowInterpolation) -> Result<Grib2Field, Grib2Error> {
+        let grid = self.grid();
+
+        let rows = match ReducedRows::new(&grid) {
+            Some(rows) => rows,
+            None => return match grid {
+                GridDefinition::LatLon(_) | GridDefinition::Gaussian(_) => Ok(self.clone()),
+                _ => Err(Grib2Error::FunctionNotImplemented)
+            }
+        };
+
+        let values = self.data_masked();
+        if values.len() != rows.counts.iter().sum::<usize>() || self.grid_template.len() < 17 {
+            return Err(Grib2Error::InvalidMessage);
+        }
+
+        let widest = rows.widest();
+        let global = rows.is_global();
+
+        // all rows share the widest row's extent, so every row has the same increment once expanded
+        let increment = rows.increments[rows.counts.iter().position(|count| *count == widest).unwrap_or(0)];
+
+        let mut expanded: Vec<Option<f32>> = Vec::with_capacity(widest * rows.counts.len());
+        let mut start = 0;
+
+        for (count, row_increment) in rows.counts.iter().zip(rows.increments.iter()) {
+            let row = &values[start..start + count];
+
+            for point in 0..widest {
+                let position = if *row_increment == 0.0 { 0.0 } else { point as f64 * increment / row_increment };
+                expanded.push(sample_row(row, position, global, method));
+            }
+
+            start += count;
+        }
+
+        // Ni, the resolution flags (i increment given), Di and the last longitude are the same in 3.0 and 3.40
+        let di_units = (increment.abs() / rows.angle_unit).round() as i64;
+        let di_sign = if rows.scanning_mode & 0x80 == 0 { 1 } else { -1 };
+
+        let mut grid_template = self.grid_template.clone();
+        grid_template[7] = widest as i64;
+        grid_template[13] |= 0x20;
+        grid_template[15] = rows.first_longitude + di_sign * di_units * (widest as i64 - 1);
+        grid_template[16] = di_units;
+
+        Ok(self.with_grid(self.grid_def, grid_template, expanded))
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{Grib2Error, Grib2Field, area::{CellRow, band_area, cell_rows}, earth::EarthModel, grid::GridDefinition, interpolate::bilinear_weights, lookup::{nearest_grid_point, point_storage_index}};
+
+/*
+Regridding of fields from one grid definition onto another.
+
+Remapping weights are worked out once for a pair of grids and can then be applied to any
+number of fields on the source grid. Each target point is a weighted sum of source points;
+missing source points are left out and the remaining weights renormalised.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegridMethod {
+    Nearest,
+    Bilinear,
+    /*
+    First order conservative remapping: each target cell is weighted by the areas of its
+    overlaps with the source cells, on the source grid's earth model. Only between plain
+    (unrotated) lat/lon and Gaussian grids, whose cells are bounded by parallels and meridians.
+    */
+    Conservative
+}
+
+#[derive(Clone)]
+pub struct RegridWeights {
+    method: RegridMethod,
+    source: GridDefinition,
+    source_point_count: usize,
+    target_number: u32,
+    target_template: Vec<i64>,
+    // (position in the source data, weight) for every target point, in target storage order
+    weights: Vec<Vec<(usize, f64)>>
+}
+
+// the source points a position falls on, with their weights, for the point remapping methods
+fn point_weights(source: &GridDefinition, earth: &EarthModel, method: RegridMethod, lat: f64, lon: f64) -> Vec<(usize, f64)> {
+    match method {
+        RegridMethod::Bilinear => bilinear_weights(source, lat, lon).unwrap_or_default(),
+        _ => nearest_grid_point(source, earth, lat, lon).map(|point| vec![(point.0, 1.0)]).unwrap_or_default()
+    }
+}
+
+// width in degrees of the overlap of two ranges of longitude, given by their centres and widths
+fn longitude_overlap(centre: f64, width: f64, other_centre: f64, other_width: f64) -> f64 {
+    let offset = (other_centre - centre + 180.0).rem_euclid(360.0) - 180.0;
+    let west = (-width / 2.0).max(offset - other_width / 2.0);
+    let east = (width / 2.0).min(offset + other_width / 2.0);
+
+    (east - west).max(0.0)
+}
+
+// the points of a row whose cells may overlap a range of longitude, given by its centre and width
+fn overlapping_points(row: &CellRow, wraps: bool, centre: f64, width: f64) -> Vec<usize> {
+    let step = row.step.abs();
+    if step == 0.0 || !step.is_finite() {
+        return (0..row.count).collect();
+    }
+
+    // fractional index of the centre along the row, and the same a turn of the globe earlier
+    let position = ((centre - row.first_longitude) * row.step.signum()).rem_euclid(360.0) / step;
+    let reach = (width / step + 1.0) / 2.0;
+
+    let mut points: Vec<usize> = [position, position - 360.0 / step]
+        .iter()
+        .flat_map(|position| (position - reach).floor() as i64..=(position + reach).ceil() as i64)
+        .map(|point| if wraps { point.rem_euclid(row.count as i64) } else { point })
+        .filter(|point| *point >= 0 && (*point as usize) < row.count)
+        .map(|point| point as usize)
+        .collect();
+
+    points.sort_unstable();
+    points.dedup();
+    points
+}
+
+/*
+Conservative weights of every target point in target storage order, as the areas of overlap of
+its cell with the source cells. None unless both grids are plain lat/lon or Gaussian grids, and
+the target has a fixed number of points per row.
+*/
+fn conservative_weights(source: &GridDefinition, target: &GridDefinition, earth: &EarthModel) -> Option<Vec<Vec<(usize, f64)>>> {
+    let (source_rows, target_rows) = (cell_rows(source)?, cell_rows(target)?);
+    let (ni, nj) = target.grid_shape()?;
+    let mode = target.scanning_mode()?;
+    let counts = source.row_point_counts()?;
+    let wraps = source.wraps_longitude();
+
+    // the source rows overlapping each target row, with the parallels bounding each overlap
+    let row_overlaps: Vec<Vec<(usize, f64, f64)>> = target_rows
+        .iter()
+        .map(|target_row| {
+            source_rows
+                .iter()
+                .enumerate()
+                .map(|(row, source_row)| (row, source_row.south.max(target_row.south), source_row.north.min(target_row.north)))
+                .filter(|(_, south, north)| north > south)
+                .collect()
+        })
+        .collect();
+
+    Some(
+        mode.point_order(ni, nj)
+            .map(|(i, j)| {
+                let centre = target_rows[j].first_longitude + i as f64 * target_rows[j].step;
+                let width = target_rows[j].step.abs();
+
+                row_overlaps[j]
+                    .iter()
+                    .flat_map(|(row, south, north)| {
+                        let source_row = &source_rows[*row];
+
+                        overlapping_points(source_row, wraps, centre, width)
+                            .into_iter()
+                            .map(|point| {
+                                let source_centre = source_row.first_longitude + point as f64 * source_row.step;
+                                let overlap = longitude_overlap(centre, width, source_centre, source_row.step.abs());
+                                (point_storage_index(source, &counts, *row, point), band_area(earth, *south, *north, overlap))
+                            })
+                            .collect::<Vec<(usize, f64)>>()
+                    })
+                    .filter(|weight| weight.1 > 0.0)
+                    .collect()
+            })
+            .collect()
+    )
+}
+
+// merges repeated source points, and scales the weights of a target point to add up to one
+fn normalised(mut weights: Vec<(usize, f64)>) -> Vec<(usize, f64)> {
+    weights.sort_by_key(|weight| weight.0);
+
+    let mut merged: Vec<(usize, f64)> = Vec::with_capacity(weights.len());
+    for (point, weight) in weights {
+        match merged.last_mut() {
+            Some(last) if last.0 == point => last.1 += weight,
+            _ => merged.push((point, weight))
+        }
+    }
+
+    let total: f64 = merged.iter().map(|weight| weight.1).sum();
+    if total > 0.0 {
+        for weight in merged.iter_mut() {
+            weight.1 /= total;
+        }
+    }
+
+    merged
+}
+
+impl RegridWeights {
+    /*
+    Works out the weights for remapping fields on the source grid onto the grid given by a grid
+    definition template number and its template values. The target grid needs a fixed number
+    of points in every row, and both grids must be supported by GridDefinition.
+    */
+    pub fn new(source: &GridDefinition, target_number: u32, target_template: &Vec<i64>, method: RegridMethod) -> Result<RegridWeights, Grib2Error> {
+        let target = GridDefinition::new(target_number, target_template);
+
+        let (ni, nj) = target.grid_shape().ok_or(Grib2Error::FunctionNotImplemented)?;
+        let mode = target.scanning_mode().ok_or(Grib2Error::FunctionNotImplemented)?;
+        let source_point_count = source.row_point_counts().ok_or(Grib2Error::FunctionNotImplemented)?.iter().sum();
+        let earth = source.earth_model().ok_or(Grib2Error::FunctionNotImplemented)?;
+
+        let weights = match method {
+            RegridMethod::Conservative => conservative_weights(source, &target, &earth)
+                .ok_or(Grib2Error::FunctionNotImplemented)?
+                .into_iter()
+                .map(normalised)
+                .collect(),
+            _ => mode
+                .point_order(ni, nj)
+                .map(|(i, j)| {
+                    let samples = target
+                        .latlon(i as f64, j as f64)
+                        .map(|(lat, lon)| point_weights(source, &earth, method, lat, lon))
+                        .unwrap_or_default();
+
+                    normalised(samples)
+                })
+                .collect()
+        };
+
+        Ok(RegridWeights {
+            method,
+            source: source.clone(),
+            source_point_count,
+            target_number,
+            target_template: target_template.clone(),
+            weights
+        })
+    }
+
+    pub fn method(&self) -> RegridMethod {
+        self.method
+    }
+
+    pub fn source(&self) -> &GridDefinition {
+        &self.source
+    }
+
+    pub fn target(&self) -> GridDefinition {
+        GridDefinition::new(self.target_number, &self.target_template)
+    }
+
+    pub fn target_point_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    // remaps a field on the source grid, target points with no source values are missing
+    pub fn apply(&self, field: &Grib2Field) -> Result<Grib2Field, Grib2Error> {
+        if field.grid() != self.source {
+            return Err(Grib2Error::GridMismatch);
+        }
+
+        let values = field.data_masked();
+        if values.len() != self.source_point_count {
+            return Err(Grib2Error::InvalidMessage);
+        }
+
+        let remapped = self.weights
+            .iter()
+            .map(|point_weights| {
+                let (sum, total) = point_weights
+                    .iter()
+                    .filter_map(|(point, weight)| Some((values.get(*point).copied()?? as f64 * weight, *weight)))
+                    .fold((0.0, 0.0), |acc, (value, weight)| (acc.0 + value, acc.1 + weight));
+
+                if total > 0.0 { Some((sum / total) as f32) } else { None }
+            })
+            .collect();
+
+        Ok(field.with_grid(self.target_number, self.target_template.clone(), remapped))
+    }
+}
+
+impl Grib2Field {
+    /*
+    Remaps the field onto the grid given by a grid definition template number and template values.
+    For remapping many fields between the same grids, build the RegridWeights once and apply them.
+    */
+    pub fn regrid(&self, target_number: u32, target_template: &Vec<i64>, method: RegridMethod) -> Result<Grib2Field, Grib2Error> {
+        RegridWeights::new(&self.grid(), target_number, target_template, method)?.apply(self)
+    }
+}
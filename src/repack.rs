@@ -0,0 +1,241 @@
+use crate::{Grib2Error, Grib2Field, Grib2Message, sections::SectionIndex};
+
+extern "C" {
+    // g2c allocates the template descriptions it hands out with malloc
+    fn free(pointer: *mut std::ffi::c_void);
+}
+
+// g2c's lookups of a template definition, by number and from the template's values
+type TemplateDefinition = unsafe extern "C" fn(i64) -> *mut libg2c_sys::gtemplate;
+type ExtendedTemplateDefinition = unsafe extern "C" fn(i64, *mut i64) -> *mut libg2c_sys::gtemplate;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepackReport {
+    pub size_before: usize,
+    pub size_after: usize,
+    pub max_abs_error: f32
+}
+
+/*
+The number of values g2c reads from a template of the given section (3, 4 or 5) and number:
+every entry of the template's definition, plus the extension implied by the values themselves.
+g2c walks the whole definition when packing a section, whatever the length of the list it is
+given, so shorter lists must be padded to this length. None for templates g2c does not know.
+*/
+pub(crate) fn template_length(section: u8, number: u16, values: &[i64]) -> Option<usize> {
+    let (base, extended): (TemplateDefinition, ExtendedTemplateDefinition) = match section {
+        3 => (libg2c_sys::getgridtemplate, libg2c_sys::extgridtemplate),
+        4 => (libg2c_sys::getpdstemplate, libg2c_sys::extpdstemplate),
+        5 => (libg2c_sys::getdrstemplate, libg2c_sys::extdrstemplate),
+        _ => return None
+    };
+
+    unsafe {
+        let definition = base(number as i64);
+        if definition.is_null() {
+            return None;
+        }
+
+        // the extension is worked out from values in the fixed part, which must all be there
+        let mut padded = values.to_vec();
+        padded.resize(values.len().max((*definition).maplen as usize), 0);
+        free(definition as *mut std::ffi::c_void);
+
+        let definition = extended(number as i64, padded.as_mut_ptr());
+        if definition.is_null() {
+            return None;
+        }
+
+        let length = ((*definition).maplen + if (*definition).needext != 0 { (*definition).extlen } else { 0 }) as usize;
+
+        if !(*definition).ext.is_null() {
+            free((*definition).ext as *mut std::ffi::c_void);
+        }
+        free(definition as *mut std::ffi::c_void);
+
+        Some(length)
+    }
+}
+
+/*
+Packs a single field into a throwaway message using g2c.
+Only Sections 5 through 7 of the result are meaningful, everything else is filler.
+*/
+unsafe fn encode_scratch(field: &Grib2Field, drt_number: u16, drt_template: &[i64]) -> Result<Vec<u8>, Grib2Error> {
+    if !field.grid_data_is_ct31() {
+        return Err(Grib2Error::FunctionNotImplemented);
+    }
+
+    // g2c only packs the points present in the bitmap, which must cover the full grid
+    let mut bitmap = match field.bitmap() {
+        Some(bitmap) => bitmap.to_flags(),
+        None if field.bitmap_indicator == 255 => Vec::new(),
+        None => return Err(Grib2Error::BitmapUnavailable)
+    };
+
+    let mut data = field.data().clone();
+    if data.len() != field.count_gridpoints as usize {
+        return Err(Grib2Error::InvalidMessage);
+    }
+
+    let mut sec0: Vec<i64> = vec![0, 2];
+    let mut sec1: Vec<i64> = vec![255, 0, 2, 0, 0, 1970, 1, 1, 0, 0, 0, 0, 0];
+
+    let mut igds: Vec<i64> = vec![
+        0,
+        field.count_gridpoints as i64,
+        field.count_optional_octets_grid as i64,
+        field.interpretation_of_optional_gridpoints as i64,
+        field.grid_def as i64
+    ];
+    let mut grid_template = field.grid_template().clone();
+    let mut deflist = field.get_grid_arrays_list().cloned().unwrap_or_default();
+
+    let mut prod_template = field.prod_template_values().clone();
+
+    // g2c also writes the reference value, scale factors and bit count back into the template
+    let drt_length = template_length(5, drt_number, drt_template).ok_or(Grib2Error::FieldEncodeError(-7))?;
+    let mut drt = drt_template.to_vec();
+    drt.resize(drt_length.max(drt_template.len()), 0);
+
+    // room for the worst case: every value stored as a full IEEE float, plus headers
+    let capacity = 8 * data.len() + bitmap.len() / 8
+        + 8 * (grid_template.len() + deflist.len() + prod_template.len() + drt.len())
+        + 4096;
+    let mut buffer: Vec<u8> = vec![0; capacity];
+
+    let create_err = libg2c_sys::g2_create(buffer.as_mut_ptr(), sec0.as_mut_ptr(), sec1.as_mut_ptr());
+    if create_err < 0 {
+        return Err(Grib2Error::MessageEncodeError(create_err as i8));
+    }
+
+    let grid_err = libg2c_sys::g2_addgrid(
+        buffer.as_mut_ptr(),
+        igds.as_mut_ptr(),
+        grid_template.as_mut_ptr(),
+        deflist.as_mut_ptr(),
+        deflist.len() as i64);
+    if grid_err < 0 {
+        return Err(Grib2Error::GridEncodeError(grid_err as i8));
+    }
+
+    let field_err = libg2c_sys::g2_addfield(
+        buffer.as_mut_ptr(),
+        field.prod_codepoint as i64,
+        prod_template.as_mut_ptr(),
+        std::ptr::null_mut(),
+        0,
+        drt_number as i64,
+        drt.as_mut_ptr(),
+        data.as_mut_ptr(),
+        field.count_gridpoints as i64,
+        if bitmap.is_empty() { 255 } else { 0 },
+        if bitmap.is_empty() { std::ptr::null_mut() } else { bitmap.as_mut_ptr() });
+    if field_err < 0 {
+        return Err(Grib2Error::FieldEncodeError(field_err as i8));
+    }
+
+    let length = libg2c_sys::g2_gribend(buffer.as_mut_ptr());
+    if length < 0 {
+        return Err(Grib2Error::MessageEncodeError(length as i8));
+    }
+
+    buffer.truncate(length as usize);
+    Ok(buffer)
+}
+
+fn repack_field(field: &Grib2Field, drt_number: u16, drt_template: &[i64]) -> Result<(Grib2Field, RepackReport, Grib2Message), Grib2Error> {
+    let scratch = Grib2Message::from_bytes(unsafe { encode_scratch(field, drt_number, drt_template)? })?;
+
+    if let Some((_, why)) = scratch.errors().first() {
+        return Err(why.clone());
+    }
+
+    let mut repacked = scratch.fields().first().ok_or(Grib2Error::Unknown)?.clone();
+    repacked.field_number = field.field_number;
+    repacked.local = field.local.clone();
+
+    let report = RepackReport {
+        size_before: field.packed_size(),
+        size_after: repacked.packed_size(),
+        max_abs_error: field.data_masked()
+            .iter()
+            .zip(repacked.data_masked().iter())
+            .filter_map(|(a, b)| Some((a.as_ref()? - b.as_ref()?).abs()))
+            .fold(0.0, f32::max)
+    };
+
+    Ok((repacked, report, scratch))
+}
+
+impl Grib2Field {
+    /*
+    Decodes the field and packs it again using the given data representation template.
+    The returned report compares the size of Sections 5 through 7 before and after.
+    */
+    pub fn repack(&self, drt_number: u16, drt_template: &[i64]) -> Result<(Grib2Field, RepackReport), Grib2Error> {
+        let (repacked, report, _) = repack_field(self, drt_number, drt_template)?;
+        Ok((repacked, report))
+    }
+}
+
+impl Grib2Message {
+    /*
+    Repacks every field in the message with the given data representation template.
+    Only Sections 5 and 7 are replaced, every other section is copied over byte for byte.
+    Bitmaps are left as they are, so fields referring to an earlier bitmap keep doing so.
+    */
+    pub fn repack(&self, drt_number: u16, drt_template: &[i64]) -> Result<(Grib2Message, RepackReport), Grib2Error> {
+        if let Some((_, why)) = self.errors().first() {
+            return Err(why.clone());
+        }
+
+        let index = SectionIndex::new(self.bytes()).ok_or(Grib2Error::InvalidMessage)?;
+
+        let mut max_abs_error: f32 = 0.0;
+        let mut packed_sections = Vec::new();
+
+        for field in self.fields() {
+            let (_, report, scratch) = repack_field(field, drt_number, drt_template)?;
+            let scratch_index = SectionIndex::new(scratch.bytes()).ok_or(Grib2Error::InvalidMessage)?;
+
+            let sec5 = scratch_index.section(5).ok_or(Grib2Error::InvalidMessage)?.range();
+            let sec7 = scratch_index.section(7).ok_or(Grib2Error::InvalidMessage)?.range();
+
+            packed_sections.push((scratch.bytes()[sec5].to_vec(), scratch.bytes()[sec7].to_vec()));
+            max_abs_error = max_abs_error.max(report.max_abs_error);
+        }
+
+        let mut raw: Vec<u8> = Vec::with_capacity(self.bytes().len());
+        let mut field = 0;
+
+        for section in index.sections() {
+            match section.number {
+                5 => raw.extend_from_slice(&packed_sections.get(field).ok_or(Grib2Error::InvalidMessage)?.0),
+                7 => {
+                    raw.extend_from_slice(&packed_sections.get(field).ok_or(Grib2Error::InvalidMessage)?.1);
+                    field += 1;
+                },
+                _ => raw.extend_from_slice(&self.bytes()[section.range()])
+            }
+        }
+
+        let total = raw.len();
+        crate::sections::write_uint(&mut raw, 8, 8, total as u64);
+
+        let mut repacked = Grib2Message::from_bytes(raw)?;
+        if let Some((_, why)) = repacked.errors().first() {
+            return Err(why.clone());
+        }
+
+        repacked.inherit_predefined_bitmaps(self);
+
+        let report = RepackReport {
+            size_before: self.bytes().len(),
+            size_after: total,
+            max_abs_error
+        };
+
+        Ok((repacked, report))
+    }
+}
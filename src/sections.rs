@@ -0,0 +1,161 @@
+/*
+Byte-level access to the sections of an encoded GRIB2 message.
+
+g2c unpacks sections into template values, but does not say where each section lives in
+the message. The editing and repacking code needs that information to splice and patch
+sections without re-encoding the rest of the message.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    pub number: u8,
+    pub offset: usize,
+    pub length: usize
+}
+
+impl Section {
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.offset..(self.offset + self.length)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SectionIndex {
+    sections: Vec<Section>
+}
+
+impl SectionIndex {
+    // Section 0 is always 16 octets, the remaining sections carry their own length.
+    pub fn new(message: &[u8]) -> Option<SectionIndex> {
+        if message.len() < 20 || &message[0..4] != b"GRIB" || message[7] != 2 {
+            return None;
+        }
+
+        let total = read_uint(message, 8, 8)? as usize;
+        if total > message.len() {
+            return None;
+        }
+
+        let mut sections = vec![Section { number: 0, offset: 0, length: 16 }];
+        let mut offset = 16;
+
+        loop {
+            if offset + 4 > total {
+                return None;
+            }
+
+            if &message[offset..offset + 4] == b"7777" {
+                sections.push(Section { number: 8, offset, length: 4 });
+                break;
+            }
+
+            let length = read_uint(message, offset, 4)? as usize;
+            if length < 5 || offset + length > total {
+                return None;
+            }
+
+            sections.push(Section {
+                number: message[offset + 4],
+                offset,
+                length
+            });
+
+            offset += length;
+        }
+
+        Some(SectionIndex {
+            sections
+        })
+    }
+
+    pub fn sections(&self) -> &Vec<Section> {
+        &self.sections
+    }
+
+    pub fn section(&self, number: u8) -> Option<&Section> {
+        self.sections.iter().find(|s| s.number == number)
+    }
+
+    // Returns the sections 4 through 7 belonging to the given (zero based) field.
+    pub fn field_sections(&self, field: usize) -> Option<Vec<&Section>> {
+        let start = self.sections
+            .iter()
+            .enumerate()
+            .filter(|s| s.1.number == 4)
+            .nth(field)?
+            .0;
+
+        Some(
+            self.sections[start..]
+            .iter()
+            .take_while(|s| s.number >= 4 && s.number <= 7)
+            .enumerate()
+            .take_while(|s| s.0 == 0 || s.1.number != 4)
+            .map(|s| s.1)
+            .collect()
+        )
+    }
+
+    // Returns the most recent occurrence of the given section at or before the field's Section 4.
+    pub fn field_section(&self, field: usize, number: u8) -> Option<&Section> {
+        let field_sections = self.field_sections(field)?;
+        let sec4 = *field_sections.first()?;
+
+        if number >= 4 {
+            return field_sections.into_iter().find(|s| s.number == number);
+        }
+
+        self.sections
+            .iter()
+            .rev()
+            .find(|s| s.offset < sec4.offset && s.number == number)
+    }
+}
+
+pub fn read_uint(bytes: &[u8], offset: usize, width: usize) -> Option<u64> {
+    Some(
+        bytes.get(offset..offset + width)?
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | *b as u64)
+    )
+}
+
+// GRIB2 stores signed integers in sign-magnitude form, with the sign in the highest bit.
+pub fn read_int(bytes: &[u8], offset: usize, width: usize) -> Option<i64> {
+    let raw = read_uint(bytes, offset, width)?;
+    let sign_bit = 1u64 << (width * 8 - 1);
+
+    if raw & sign_bit != 0 {
+        Some(-((raw & !sign_bit) as i64))
+    } else {
+        Some(raw as i64)
+    }
+}
+
+// Returns false when the value does not fit in the given width.
+pub fn write_uint(bytes: &mut [u8], offset: usize, width: usize, value: u64) -> bool {
+    if width < 8 && value >> (width * 8) != 0 {
+        return false;
+    }
+
+    if let Some(dest) = bytes.get_mut(offset..offset + width) {
+        for (i, b) in dest.iter_mut().enumerate() {
+            *b = (value >> ((width - 1 - i) * 8)) as u8;
+        }
+
+        true
+    } else {
+        false
+    }
+}
+
+pub fn write_int(bytes: &mut [u8], offset: usize, width: usize, value: i64) -> bool {
+    let magnitude = value.unsigned_abs();
+    let sign_bit = 1u64 << (width * 8 - 1);
+
+    if magnitude >= sign_bit {
+        return false;
+    }
+
+    write_uint(bytes, offset, width, if value < 0 { magnitude | sign_bit } else { magnitude })
+}
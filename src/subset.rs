@@ -0,0 +1,215 @@
+use std::ops::Range;
+
+use crate::{Grib2Error, Grib2Field, grid::{GridCoordinates, GridDefinition}};
+
+/*
+Cropping of fields to a latitude/longitude box or a window of grid indices.
+
+Fields keep their scanning mode, and the grid definition template is rewritten to describe
+the window that was kept. Global lat/lon and Gaussian grids may be cropped across the
+longitude where their rows start, e.g. around the prime meridian on a 0 to 360 grid.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub north: f64,
+    pub south: f64,
+    // a box whose western edge is east of its eastern edge crosses the date line
+    pub west: f64,
+    pub east: f64
+}
+
+impl BoundingBox {
+    pub fn new(north: f64, south: f64, west: f64, east: f64) -> BoundingBox {
+        BoundingBox {
+            north,
+            south,
+            west,
+            east
+        }
+    }
+
+    pub fn contains_latitude(&self, lat: f64) -> bool {
+        lat >= self.south - 1e-9 && lat <= self.north + 1e-9
+    }
+
+    // longitudes are compared modulo 360 degrees, whichever convention either side uses
+    pub fn contains_longitude(&self, lon: f64) -> bool {
+        let width = if self.east - self.west >= 360.0 { 360.0 } else { (self.east - self.west).rem_euclid(360.0) };
+        (lon - self.west + 1e-9).rem_euclid(360.0) <= width + 2e-9
+    }
+
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        self.contains_latitude(lat) && self.contains_longitude(lon)
+    }
+}
+
+// the smallest contiguous run of indices holding every selected one, wrapping around if allowed
+fn index_run(selected: &[bool], wraps: bool) -> Option<Vec<usize>> {
+    let count = selected.len();
+    let first = selected.iter().position(|chosen| *chosen)?;
+    let last = selected.iter().rposition(|chosen| *chosen)?;
+
+    if !wraps || selected.iter().all(|chosen| *chosen) {
+        return Some((first..=last).collect());
+    }
+
+    // on a wrapping row the run starts after the longest gap between selected indices
+    let mut start = first;
+    let mut longest_gap = 0;
+    let mut gap = 0;
+
+    for index in (last + 1..last + 1 + count).map(|index| index % count) {
+        if selected[index] {
+            if gap > longest_gap {
+                longest_gap = gap;
+                start = index;
+            }
+            gap = 0;
+        } else {
+            gap += 1;
+        }
+    }
+
+    Some((0..count - longest_gap).map(|offset| (start + offset) % count).collect())
+}
+
+// the columns and rows (in index order) of the smallest window of the grid covering a box
+fn box_window(grid: &GridDefinition, bbox: &BoundingBox) -> Option<(Vec<usize>, Vec<usize>)> {
+    let (ni, nj) = grid.grid_shape()?;
+
+    match (grid, grid.coordinates()?) {
+        // rows and columns of plain lat/lon and Gaussian grids can be picked independently
+        (GridDefinition::LatLon(_), GridCoordinates::Axes { latitudes, longitudes }) |
+        (GridDefinition::Gaussian(_), GridCoordinates::Axes { latitudes, longitudes }) => {
+            let rows: Vec<bool> = latitudes.iter().map(|lat| bbox.contains_latitude(*lat)).collect();
+            let columns: Vec<bool> = longitudes.iter().map(|lon| bbox.contains_longitude(*lon)).collect();
+
+            Some((index_run(&columns, grid.wraps_longitude())?, index_run(&rows, false)?))
+        },
+        _ => {
+            let mut columns = vec![false; ni];
+            let mut rows = vec![false; nj];
+
+            for (j, row) in rows.iter_mut().enumerate() {
+                for (i, column) in columns.iter_mut().enumerate() {
+                    if grid.latlon(i as f64, j as f64).is_some_and(|(lat, lon)| bbox.contains(lat, lon)) {
+                        *column = true;
+                        *row = true;
+                    }
+                }
+            }
+
+            Some((index_run(&columns, false)?, index_run(&rows, false)?))
+        }
+    }
+}
+
+// a longitude in template units, kept in the same convention as the grid's first longitude
+fn longitude_units(lon: f64, unit: f64, signed: bool) -> i64 {
+    let lon = if signed { (lon + 180.0).rem_euclid(360.0) - 180.0 } else { lon.rem_euclid(360.0) };
+    (lon / unit).round() as i64
+}
+
+/*
+The template of the window of the grid made of the given columns and rows, or None for
+templates which cannot describe a window of themselves.
+*/
+fn window_template(grid_template: &[i64], grid: &GridDefinition, columns: &[usize], rows: &[usize]) -> Option<Vec<i64>> {
+    let (first_i, last_i) = (*columns.first()? as f64, *columns.last()? as f64);
+    let (first_j, last_j) = (*rows.first()? as f64, *rows.last()? as f64);
+
+    let mut template = grid_template.to_vec();
+    *template.get_mut(7)? = columns.len() as i64;
+    *template.get_mut(8)? = rows.len() as i64;
+
+    // (template index of the first latitude, of the last latitude or None, unit, the corner points)
+    let (first, last, unit, corners) = match grid {
+        GridDefinition::LatLon(def) => (11, Some(14), def.angle_unit(), (def.latlon(first_i, first_j), def.latlon(last_i, last_j))),
+        GridDefinition::RotatedLatLon(def) => (11, Some(14), def.rotated_grid.angle_unit(), (
+            def.rotated_grid.latlon(first_i, first_j),
+            def.rotated_grid.latlon(last_i, last_j)
+        )),
+        GridDefinition::Gaussian(def) => (11, Some(14), def.angle_unit(), (def.latlon(first_i, first_j), def.latlon(last_i, last_j))),
+        GridDefinition::Mercator(def) => (9, Some(13), 1e-6, (def.latlon(first_i, first_j), def.latlon(last_i, last_j))),
+        GridDefinition::PolarStereographic(def) => (9, None, 1e-6, (def.latlon(first_i, first_j), def.latlon(last_i, last_j))),
+        GridDefinition::LambertConformal(def) => (9, None, 1e-6, (def.latlon(first_i, first_j), def.latlon(last_i, last_j))),
+        // the sector origin moves with the window, the rest of the view is unchanged
+        GridDefinition::SpaceView(_) => {
+            *template.get_mut(19)? += first_i as i64;
+            *template.get_mut(20)? += first_j as i64;
+            return Some(template);
+        },
+        _ => return None
+    };
+
+    // longitudes keep the grid's convention, 0 to 360 or -180 to 180
+    let signed = *grid_template.get(first + 1)? < 0;
+    let ((lat1, lon1), (lat2, lon2)) = corners;
+
+    *template.get_mut(first)? = (lat1 / unit).round() as i64;
+    *template.get_mut(first + 1)? = longitude_units(lon1, unit, signed);
+
+    if let Some(last) = last {
+        *template.get_mut(last)? = (lat2 / unit).round() as i64;
+        *template.get_mut(last + 1)? = longitude_units(lon2, unit, signed);
+    }
+
+    Some(template)
+}
+
+impl Grib2Field {
+    /*
+    Crops the field to the grid points inside a latitude/longitude box given in degrees.
+    Lat/lon and Gaussian grids are cut to exactly the points inside the box, wrapping around
+    global grids where the box crosses the start of the rows. Projected grids are cut to the
+    smallest window of grid indices holding every point inside the box, see subset_indices().
+    */
+    pub fn subset(&self, bbox: &BoundingBox) -> Result<Grib2Field, Grib2Error> {
+        let grid = self.grid();
+
+        if grid.grid_shape().is_none() || !grid.is_supported() {
+            return Err(Grib2Error::FunctionNotImplemented);
+        }
+
+        let (columns, rows) = box_window(&grid, bbox).ok_or(Grib2Error::EmptySubset)?;
+        self.crop(&grid, &columns, &rows)
+    }
+
+    /*
+    Crops the field to a window of grid point indices, counted from the first grid point in the
+    scanning directions as in GridDefinition::latlon(). Works on every template with a fixed
+    number of points per row except the staggered Arakawa grids.
+    */
+    pub fn subset_indices(&self, columns: Range<usize>, rows: Range<usize>) -> Result<Grib2Field, Grib2Error> {
+        let grid = self.grid();
+        let (ni, nj) = grid.grid_shape().ok_or(Grib2Error::FunctionNotImplemented)?;
+
+        if columns.start >= columns.end || rows.start >= rows.end || columns.end > ni || rows.end > nj {
+            return Err(Grib2Error::EmptySubset);
+        }
+
+        self.crop(&grid, &columns.collect::<Vec<usize>>(), &rows.collect::<Vec<usize>>())
+    }
+
+    // the field cut down to the given columns and rows, which are listed in index order
+    pub(crate) fn crop(&self, grid: &GridDefinition, columns: &[usize], rows: &[usize]) -> Result<Grib2Field, Grib2Error> {
+        let (ni, nj) = grid.grid_shape().ok_or(Grib2Error::FunctionNotImplemented)?;
+        let mode = grid.scanning_mode().ok_or(Grib2Error::FunctionNotImplemented)?;
+
+        let template = window_template(&self.grid_template, grid, columns, rows)
+            .ok_or(Grib2Error::FunctionNotImplemented)?;
+
+        let values = self.data_masked();
+        if values.len() != ni * nj {
+            return Err(Grib2Error::InvalidMessage);
+        }
+
+        let cropped = mode
+            .point_order(columns.len(), rows.len())
+            .map(|(i, j)| values[mode.storage_index(columns[i], rows[j], ni, nj)])
+            .collect();
+
+        Ok(self.with_grid(self.grid_def, template, cropped))
+    }
+}
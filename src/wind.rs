@@ -0,0 +1,110 @@
+use crate::{Grib2Error, Grib2Field, grid::{GridDefinition, ScanningMode}};
+
+/*
+Rotation of grid-relative vector fields to earth-relative components.
+
+When bit 5 of the resolution and component flags (code table 3.3) is set, the u and v
+components of a vector follow the x and y axes of the grid rather than east and north.
+On conformal projections those axes are turned from east and north by the convergence of
+the meridians, which changes from point to point.
+*/
+
+// the template index of the resolution and component flags
+fn flags_index(grid: &GridDefinition) -> Option<usize> {
+    match grid {
+        GridDefinition::LatLon(_) |
+        GridDefinition::RotatedLatLon(_) |
+        GridDefinition::Gaussian(_) |
+        GridDefinition::ArakawaRotatedLatLon(_) => Some(13),
+        GridDefinition::Mercator(_) |
+        GridDefinition::PolarStereographic(_) |
+        GridDefinition::LambertConformal(_) |
+        GridDefinition::SpaceView(_) => Some(11),
+        _ => None
+    }
+}
+
+/*
+The angle in degrees that the grid's x and y axes are turned clockwise from east and north,
+where x and y increase with i and j whatever the scanning directions. Grids which are not
+described by a projection of their own are measured from the positions of neighbouring points.
+*/
+fn axis_rotation(grid: &GridDefinition, mode: &ScanningMode, i: f64, j: f64) -> Option<f64> {
+    if let GridDefinition::LatLon(_) | GridDefinition::Gaussian(_) = grid {
+        return Some(0.0);
+    }
+
+    let (_, lon) = grid.latlon(i, j)?;
+
+    match grid {
+        GridDefinition::LambertConformal(def) => Some(def.projection().convergence(lon)),
+        GridDefinition::PolarStereographic(def) => Some(def.projection().convergence(lon)),
+        GridDefinition::Mercator(def) => Some(-(def.grid_orientation as f64 * 1e-6)),
+        _ => {
+            // a step along x, going against i where the grid scans towards -x
+            let step = if mode.i_negative { -0.5 } else { 0.5 };
+            let (from, to) = (grid.latlon(i - step, j)?, grid.latlon(i + step, j)?);
+
+            let east = ((to.1 - from.1 + 180.0).rem_euclid(360.0) - 180.0) * ((from.0 + to.0) / 2.0).to_radians().cos();
+            let north = to.0 - from.0;
+
+            Some(-north.atan2(east).to_degrees())
+        }
+    }
+}
+
+/*
+Turns a pair of u and v fields on the same grid into earth-relative (eastward and northward)
+components, and clears the grid-relative flag of both grid definitions. Fields which are
+already earth-relative are returned unchanged. Points missing from either field are missing
+from both results.
+*/
+pub fn earth_relative_winds(u: &Grib2Field, v: &Grib2Field) -> Result<(Grib2Field, Grib2Field), Grib2Error> {
+    let grid = u.grid();
+    if v.grid() != grid {
+        return Err(Grib2Error::GridMismatch);
+    }
+
+    let flags = grid.resolution_flags().ok_or(Grib2Error::FunctionNotImplemented)?;
+    if !flags.grid_relative_vectors {
+        return Ok((u.clone(), v.clone()));
+    }
+
+    let (ni, nj) = grid.grid_shape().ok_or(Grib2Error::FunctionNotImplemented)?;
+    let mode = grid.scanning_mode().ok_or(Grib2Error::FunctionNotImplemented)?;
+    let index = flags_index(&grid).ok_or(Grib2Error::FunctionNotImplemented)?;
+
+    let (u_values, v_values) = (u.data_masked(), v.data_masked());
+    if u_values.len() != ni * nj || v_values.len() != ni * nj {
+        return Err(Grib2Error::InvalidMessage);
+    }
+
+    let mut eastward: Vec<Option<f32>> = Vec::with_capacity(ni * nj);
+    let mut northward: Vec<Option<f32>> = Vec::with_capacity(ni * nj);
+
+    for (point, (i, j)) in mode.point_order(ni, nj).enumerate() {
+        let rotation = axis_rotation(&grid, &mode, i as f64, j as f64);
+
+        match (u_values[point], v_values[point], rotation) {
+            (Some(u), Some(v), Some(rotation)) => {
+                let (sin, cos) = rotation.to_radians().sin_cos();
+                let (u, v) = (u as f64, v as f64);
+
+                eastward.push(Some((cos * u + sin * v) as f32));
+                northward.push(Some((cos * v - sin * u) as f32));
+            },
+            _ => {
+                eastward.push(None);
+                northward.push(None);
+            }
+        }
+    }
+
+    let mut template = u.grid_template().clone();
+    template[index] &= !0x08;
+
+    Ok((
+        u.with_grid(u.grid_def, template.clone(), eastward),
+        v.with_grid(v.grid_def, template, northward)
+    ))
+}